@@ -1,4 +1,17 @@
-use crate::input::config::{Config, Input};
+use std::path::PathBuf;
+
+use crate::{
+    aggregation::{
+        ratchet::CoverageDrop,
+        thresholds::{ThresholdViolation, ThresholdsCheck},
+    },
+    core::{AggregatedCoverage, AggregatedCoverageCounters},
+    html::colors::render_optional_percentage,
+    input::{
+        config::{Config, Input},
+        filters::RemovedFiles,
+    },
+};
 
 #[cfg(test)]
 use mockall::automock;
@@ -23,20 +36,31 @@ impl<TConsole: Printer> CliOutput<TConsole> {
         CliOutput { console }
     }
 
-    fn print_status(&self, title: &str, status: &str) {
+    /// Print a message after its title, right aligned and colored
+    fn print_titled(&self, color: &str, title: &str, message: &str) {
         const BOLD: &str = "\x1b[1m";
-        const GREEN: &str = "\x1b[32m";
         const RESET: &str = "\x1b[0m";
-        let message = format!("{}{}{: >12} {}{}", BOLD, GREEN, title, RESET, status);
+        let message = format!("{}{}{: >12} {}{}", BOLD, color, title, RESET, message);
         self.console.println(&message);
     }
 
-    pub fn print_error(&self, error: &str) {
-        const BOLD: &str = "\x1b[1m";
+    fn print_status(&self, title: &str, status: &str) {
+        const GREEN: &str = "\x1b[32m";
+        self.print_titled(GREEN, title, status);
+    }
+
+    fn print_failure(&self, title: &str, failure: &str) {
         const RED: &str = "\x1b[31m";
-        const RESET: &str = "\x1b[0m";
-        let message = format!("{}{}{: >12} {}{}", BOLD, RED, "Error", RESET, error);
-        self.console.println(&message);
+        self.print_titled(RED, title, failure);
+    }
+
+    pub fn print_error(&self, error: &str) {
+        self.print_failure("Error", error);
+    }
+
+    pub fn print_warning(&self, warning: &str) {
+        const YELLOW: &str = "\x1b[33m";
+        self.print_titled(YELLOW, "Warning", warning);
     }
 
     fn print_input(&self, input: &Input) {
@@ -53,7 +77,7 @@ impl<TConsole: Printer> CliOutput<TConsole> {
         );
     }
 
-    pub fn print_introduction(&self, config: &Config) {
+    pub fn print_introduction(&self, config: &Config, removed_files: &RemovedFiles) {
         self.print_status(
             "Generating",
             &format!(
@@ -64,10 +88,182 @@ impl<TConsole: Printer> CliOutput<TConsole> {
 
         self.print_status("", &format!("Report name: '{}'", config.name));
         self.print_status("", &format!("Reporter: '{}'", config.reporter.to_str()));
+        self.print_inputs(config, removed_files);
+    }
+
+    /// Introduction of the check command, which renders no report
+    pub fn print_check_introduction(&self, config: &Config, removed_files: &RemovedFiles) {
+        self.print_status(
+            "Checking",
+            &format!("Coverage of {} input(s) lcov files", config.inputs.len()),
+        );
+        self.print_inputs(config, removed_files);
+    }
+
+    fn print_inputs(&self, config: &Config, removed_files: &RemovedFiles) {
         self.print_status("", "Inputs: ");
         for input in config.inputs.iter() {
             self.print_input(input);
         }
+        if let Some(baseline) = &config.baseline {
+            self.print_status("", &format!("Baseline: {}", baseline.display()));
+        }
+        if let Some(source_rev) = &config.source_rev {
+            self.print_status("", &format!("Sources: revision {}", source_rev));
+        }
+
+        if !removed_files.is_empty() {
+            self.print_status("", "Filters: ");
+            for (filter, count) in removed_files.iter() {
+                self.print_status("", &format!("  - {}: {} file(s) removed", filter, count));
+            }
+        }
+    }
+
+    pub fn print_merged_report(&self, path: &str) {
+        self.print_status("Merged", &format!("Merged LCOV report written to {}", path));
+    }
+
+    /// Coverage of the lines changed by the diff, when it is reported
+    pub fn print_changed_lines(&self, coverage: &AggregatedCoverage) {
+        if let Some(changed_lines) = &coverage.changed_lines {
+            self.print_status(
+                "Diff",
+                &format!(
+                    "{}/{} changed line(s) covered ({})",
+                    changed_lines.covered_count,
+                    changed_lines.count,
+                    render_optional_percentage(changed_lines.percentage())
+                ),
+            );
+        }
+    }
+
+    pub fn print_coverage(&self, coverage: &AggregatedCoverage) {
+        let render_counters = |counters: &AggregatedCoverageCounters| {
+            format!(
+                "{}/{} ({})",
+                counters.covered_count,
+                counters.count,
+                render_optional_percentage(counters.percentage())
+            )
+        };
+        self.print_status(
+            "Coverage",
+            &format!(
+                "Lines {}, Functions {}, Branches {}",
+                render_counters(&coverage.lines),
+                render_counters(&coverage.functions),
+                render_counters(&coverage.branches)
+            ),
+        );
+    }
+
+    fn render_violation(violation: &ThresholdViolation) -> String {
+        format!(
+            "{}: {:.2}% covered, {:.2}% required",
+            violation.kind, violation.percentage, violation.threshold
+        )
+    }
+
+    /// Outcome of the thresholds check, when thresholds or threshold rules
+    /// are given
+    pub fn print_thresholds(&self, check: &ThresholdsCheck) {
+        if !check.is_checked {
+            return;
+        }
+        if check.is_reached() {
+            self.print_status("Passed", "Coverage reaches all thresholds");
+            return;
+        }
+        if !check.violations.is_empty() {
+            self.print_failure(
+                "Failed",
+                &format!("Coverage is below {} threshold(s):", check.violations.len()),
+            );
+            for violation in check.violations.iter() {
+                self.print_status("", &format!("  - {}", Self::render_violation(violation)));
+            }
+        }
+        if !check.rule_violations.is_empty() {
+            self.print_failure(
+                "Failed",
+                &format!(
+                    "{} module(s) or file(s) below the thresholds of their rule:",
+                    check.rule_violations.len()
+                ),
+            );
+            for rule_violations in check.rule_violations.iter() {
+                for violation in rule_violations.violations.iter() {
+                    self.print_status(
+                        "",
+                        &format!(
+                            "  - {}, {} (rule '{}')",
+                            rule_violations.path,
+                            Self::render_violation(violation),
+                            rule_violations.pattern
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Modules whose coverage dropped below the one stored in the ratchet
+    /// file, the empty path being the whole report
+    pub fn print_coverage_drops(&self, drops: &[CoverageDrop]) {
+        if drops.is_empty() {
+            self.print_status("Ratchet", "No coverage drop since the ratchet file");
+            return;
+        }
+        self.print_failure(
+            "Failed",
+            &format!("{} module(s) dropped below the ratchet file:", drops.len()),
+        );
+        for drop in drops {
+            let path = match drop.path.as_str() {
+                "" => "whole report",
+                path => path,
+            };
+            for violation in drop.violations.iter() {
+                self.print_status(
+                    "",
+                    &format!(
+                        "  - {}, {}: {:.2}% covered, {:.2}% stored",
+                        path, violation.kind, violation.percentage, violation.threshold
+                    ),
+                );
+            }
+        }
+    }
+
+    pub fn print_ratchet_raised(&self, path: &str) {
+        self.print_status(
+            "Raised",
+            &format!("Ratchet file {} raised to the coverage", path),
+        );
+    }
+
+    pub fn print_ratchet_hint(&self) {
+        self.print_status(
+            "Hint",
+            "Coverage went up or modules changed, run with --update to raise the ratchet file",
+        );
+    }
+
+    /// Pages of the files whose source is missing only list the lines of the
+    /// report
+    pub fn print_missing_sources(&self, missing_sources: &[PathBuf]) {
+        if missing_sources.is_empty() {
+            return;
+        }
+        self.print_warning(&format!(
+            "{} source file(s) could not be read, their pages only list the lines of the report:",
+            missing_sources.len()
+        ));
+        for source in missing_sources {
+            self.print_status("", &format!("  - {}", source.display()));
+        }
     }
 
     pub fn print_conclusion(&self, output: &str) {
@@ -82,7 +278,10 @@ impl<TConsole: Printer> CliOutput<TConsole> {
         match command {
             "report" => self.console.println(include_str!("help.report.txt")),
             "to-file" => self.console.println(include_str!("help.to-file.txt")),
+            "merge" => self.console.println(include_str!("help.merge.txt")),
             "from-file" => self.console.println(include_str!("help.from-file.txt")),
+            "check" => self.console.println(include_str!("help.check.txt")),
+            "ratchet" => self.console.println(include_str!("help.ratchet.txt")),
             _ => self.console.println("Unknown command"),
         }
     }
@@ -90,9 +289,7 @@ impl<TConsole: Printer> CliOutput<TConsole> {
 
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
-
-    use crate::input::config::Reporter;
+    use crate::{aggregation::thresholds::RuleViolations, input::config::Reporter};
 
     use super::*;
 
@@ -104,6 +301,7 @@ mod test {
                     let message = message.replace("\x1b[1m", "");
                     let message = message.replace("\x1b[32m", "");
                     let message = message.replace("\x1b[31m", "");
+                    let message = message.replace("\x1b[33m", "");
                     let message = message.replace("\x1b[0m", "");
                     let message = message.trim();
                     return message == m;
@@ -123,26 +321,241 @@ mod test {
             .expect("- test1: test1/test1")
             .expect("- test2");
 
-        CliOutput::new(console).print_introduction(&Config {
-            name: "test".to_string(),
-            inputs: vec![
-                Input::from_name_and_path("test1".to_string(), PathBuf::from("test1/test1")),
-                Input::from_path(PathBuf::from("test2")),
+        CliOutput::new(console).print_introduction(
+            &Config {
+                name: "test".to_string(),
+                inputs: vec![
+                    Input::from_name_and_path("test1".to_string(), PathBuf::from("test1/test1")),
+                    Input::from_path(PathBuf::from("test2")),
+                ],
+                output: PathBuf::from("test"),
+                reporter: Reporter::default(),
+                ..Default::default()
+            },
+            &RemovedFiles::new(),
+        );
+    }
+
+    #[test]
+    fn when_files_were_removed_introduction_shall_count_them_per_filter() {
+        let console = MockPrinter::new()
+            .expect("Generating HTML report for 1 input(s) lcov files")
+            .expect("Report name: 'test'")
+            .expect("Reporter: 'html-full-light'")
+            .expect("Inputs:")
+            .expect("- test1")
+            .expect("Filters:")
+            .expect("- exclude '**/vendor/**': 12 file(s) removed")
+            .expect("- include '**/src/**': 3 file(s) removed");
+
+        CliOutput::new(console).print_introduction(
+            &Config {
+                name: "test".to_string(),
+                inputs: vec![Input::from_path(PathBuf::from("test1"))],
+                ..Default::default()
+            },
+            &RemovedFiles::from([
+                ("include '**/src/**'".to_string(), 3),
+                ("exclude '**/vendor/**'".to_string(), 12),
+            ]),
+        );
+    }
+
+    #[test]
+    fn when_sources_are_read_from_a_revision_introduction_shall_give_it() {
+        let console = MockPrinter::new()
+            .expect("Generating HTML report for 1 input(s) lcov files")
+            .expect("Report name: 'test'")
+            .expect("Reporter: 'html-full-light'")
+            .expect("Inputs:")
+            .expect("- test1")
+            .expect("Sources: revision 1a2b3c4");
+
+        CliOutput::new(console).print_introduction(
+            &Config {
+                name: "test".to_string(),
+                inputs: vec![Input::from_path(PathBuf::from("test1"))],
+                source_rev: Some("1a2b3c4".to_string()),
+                ..Default::default()
+            },
+            &RemovedFiles::new(),
+        );
+    }
+
+    #[test]
+    fn when_the_coverage_of_a_diff_is_reported_its_changed_lines_shall_be_printed() {
+        let console = MockPrinter::new().expect("Diff 3/4 changed line(s) covered (75.00%)");
+        CliOutput::new(console).print_changed_lines(&AggregatedCoverage {
+            changed_lines: Some(AggregatedCoverageCounters::new(4, 3)),
+            ..Default::default()
+        });
+
+        CliOutput::new(MockPrinter::new()).print_changed_lines(&AggregatedCoverage::default());
+    }
+
+    #[test]
+    fn when_checking_coverage_introduction_shall_not_mention_a_report() {
+        let console = MockPrinter::new()
+            .expect("Checking Coverage of 1 input(s) lcov files")
+            .expect("Inputs:")
+            .expect("- test1");
+
+        CliOutput::new(console).print_check_introduction(
+            &Config {
+                name: "test".to_string(),
+                inputs: vec![Input::from_path(PathBuf::from("test1"))],
+                ..Default::default()
+            },
+            &RemovedFiles::new(),
+        );
+    }
+
+    #[test]
+    fn test_print_coverage() {
+        let console = MockPrinter::new()
+            .expect("Coverage Lines 3/4 (75.00%), Functions 0/0 (-), Branches 1/2 (50.00%)");
+        CliOutput::new(console).print_coverage(&AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 3),
+            branches: AggregatedCoverageCounters::new(2, 1),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn when_thresholds_are_not_reached_each_violation_shall_be_listed() {
+        let console = MockPrinter::new()
+            .expect("Failed Coverage is below 2 threshold(s):")
+            .expect("- Lines: 66.67% covered, 80.00% required")
+            .expect("- Branches: 50.00% covered, 60.00% required");
+        CliOutput::new(console).print_thresholds(&ThresholdsCheck {
+            is_checked: true,
+            violations: vec![
+                ThresholdViolation {
+                    kind: "Lines",
+                    threshold: 80.,
+                    percentage: 66.666_67,
+                },
+                ThresholdViolation {
+                    kind: "Branches",
+                    threshold: 60.,
+                    percentage: 50.,
+                },
             ],
-            output: PathBuf::from("test"),
-            reporter: Reporter::default(),
+            ..Default::default()
         });
     }
 
+    #[test]
+    fn when_threshold_rules_are_not_reached_each_violation_shall_name_its_rule() {
+        let console = MockPrinter::new()
+            .expect("Failed 1 module(s) or file(s) below the thresholds of their rule:")
+            .expect("- core/parser.rs, Lines: 85.00% covered, 90.00% required (rule 'core/**')")
+            .expect(
+                "- core/parser.rs, Functions: 50.00% covered, 75.00% required (rule 'core/**')",
+            );
+        CliOutput::new(console).print_thresholds(&ThresholdsCheck {
+            is_checked: true,
+            rule_violations: vec![RuleViolations {
+                path: "core/parser.rs".to_string(),
+                pattern: "core/**".to_string(),
+                violations: vec![
+                    ThresholdViolation {
+                        kind: "Lines",
+                        threshold: 90.,
+                        percentage: 85.,
+                    },
+                    ThresholdViolation {
+                        kind: "Functions",
+                        threshold: 75.,
+                        percentage: 50.,
+                    },
+                ],
+            }],
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn when_thresholds_are_reached_or_not_given_it_shall_be_printed_only_if_given() {
+        let console = MockPrinter::new().expect("Passed Coverage reaches all thresholds");
+        CliOutput::new(console).print_thresholds(&ThresholdsCheck {
+            is_checked: true,
+            ..Default::default()
+        });
+
+        CliOutput::new(MockPrinter::new()).print_thresholds(&ThresholdsCheck::default());
+    }
+
+    #[test]
+    fn when_coverage_dropped_below_the_ratchet_each_drop_shall_be_listed() {
+        let console = MockPrinter::new()
+            .expect("Failed 2 module(s) dropped below the ratchet file:")
+            .expect("- whole report, Functions: 40.00% covered, 50.00% stored")
+            .expect("- core, Lines: 85.00% covered, 90.00% stored");
+        CliOutput::new(console).print_coverage_drops(&[
+            CoverageDrop {
+                path: "".to_string(),
+                violations: vec![ThresholdViolation {
+                    kind: "Functions",
+                    threshold: 50.,
+                    percentage: 40.,
+                }],
+            },
+            CoverageDrop {
+                path: "core".to_string(),
+                violations: vec![ThresholdViolation {
+                    kind: "Lines",
+                    threshold: 90.,
+                    percentage: 85.,
+                }],
+            },
+        ]);
+    }
+
+    #[test]
+    fn when_coverage_did_not_drop_below_the_ratchet_it_shall_be_printed() {
+        let console = MockPrinter::new().expect("Ratchet No coverage drop since the ratchet file");
+        CliOutput::new(console).print_coverage_drops(&[]);
+    }
+
     #[test]
     fn test_print_conclusion() {
         let console = MockPrinter::new().expect("Success Report generated at test");
         CliOutput::new(console).print_conclusion("test");
     }
 
+    #[test]
+    fn when_sources_are_missing_they_shall_be_listed() {
+        let console = MockPrinter::new()
+            .expect("Warning 2 source file(s) could not be read, their pages only list the lines of the report:")
+            .expect("- /project/src/main.cpp")
+            .expect("- /project/src/lib.cpp");
+        CliOutput::new(console).print_missing_sources(&[
+            PathBuf::from("/project/src/main.cpp"),
+            PathBuf::from("/project/src/lib.cpp"),
+        ]);
+    }
+
+    #[test]
+    fn when_no_source_is_missing_nothing_shall_be_printed() {
+        CliOutput::new(MockPrinter::new()).print_missing_sources(&[]);
+    }
+
+    #[test]
+    fn test_print_merged_report() {
+        let console = MockPrinter::new().expect("Merged Merged LCOV report written to merged.info");
+        CliOutput::new(console).print_merged_report("merged.info");
+    }
+
     #[test]
     fn text_print_error() {
         let console = MockPrinter::new().expect("Error test");
         CliOutput::new(console).print_error("test");
     }
+
+    #[test]
+    fn text_print_warning() {
+        let console = MockPrinter::new().expect("Warning test");
+        CliOutput::new(console).print_warning("test");
+    }
 }
@@ -1,15 +1,28 @@
 use std::path::PathBuf;
 
-use crate::input::config::{Config, Input, Reporter};
+use crate::input::config::{Config, ExclusionMarkers, Input, PathMapping, Reporter, STDIN_PATH};
 
 #[derive(Debug, PartialEq)]
 pub enum CliCommand {
     Report(Config),
     FromFile(PathBuf),
     ToFile(PathBuf, Config),
+    Merge(PathBuf, Config),
+    Check(Config),
+    Ratchet(PathBuf, RatchetOptions, Config),
     Help(String),
 }
 
+/// How the coverage is compared with the ratchet file
+#[derive(Debug, PartialEq, Default)]
+pub struct RatchetOptions {
+    /// Percentage points the coverage may drop by
+    pub tolerance: f32,
+
+    /// Whether the ratchet file shall be raised to the current coverage
+    pub update: bool,
+}
+
 #[derive(Debug, PartialEq, Default)]
 pub struct CliConfigParser {
     reporter: Option<Reporter>,
@@ -20,6 +33,22 @@ pub struct CliConfigParser {
     output: Option<PathBuf>,
     command: Option<String>,
     config_file: Option<PathBuf>,
+    merged_report: Option<PathBuf>,
+    keep_going: bool,
+    no_markers: bool,
+    path_mappings: Vec<PathMapping>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    source_roots: Vec<PathBuf>,
+    source_rev: Option<String>,
+    diff: Option<PathBuf>,
+    diff_base: Option<String>,
+    baseline: Option<PathBuf>,
+    fail_under_lines: Option<f32>,
+    fail_under_functions: Option<f32>,
+    fail_under_branches: Option<f32>,
+    ratchet_file: Option<PathBuf>,
+    ratchet_options: RatchetOptions,
     help: Option<String>,
 }
 impl CliConfigParser {
@@ -34,7 +63,9 @@ impl CliConfigParser {
         if self.detect_help() {
             self.command = Some("help".to_string());
             match command.as_str() {
-                "report" | "from-file" | "to-file" => self.help = Some(command),
+                "report" | "from-file" | "to-file" | "merge" | "check" | "ratchet" => {
+                    self.help = Some(command)
+                }
                 _ => self.help = Some("".to_string()),
             }
             return Ok(self);
@@ -61,6 +92,23 @@ impl CliConfigParser {
                 self.parse_report_command()?;
                 return Ok(self);
             }
+            "merge" => {
+                let merged_report = self.get_next_value("merge")?;
+                self.merged_report = Some(PathBuf::from(merged_report));
+                self.command = Some("merge".to_string());
+                self.parse_report_command()?;
+                return Ok(self);
+            }
+            "check" => {
+                self.command = Some("check".to_string());
+                self.parse_report_command()?
+            }
+            "ratchet" => {
+                let ratchet_file = self.get_next_value("ratchet")?;
+                self.ratchet_file = Some(PathBuf::from(ratchet_file));
+                self.command = Some("ratchet".to_string());
+                self.parse_report_command()?
+            }
             "help" => {
                 self.command = Some("help".to_string());
                 self.help = Some("".to_string());
@@ -83,6 +131,47 @@ impl CliConfigParser {
                 "--name" | "-n" => self.set_name(arg_str)?,
                 "--input" | "-i" => self.add_input(arg_str)?,
                 "--output" | "-o" => self.set_output(arg_str)?,
+                "--keep-going" | "-k" => self.keep_going = true,
+                "--no-markers" => self.no_markers = true,
+                "--map-path" => self.add_path_mapping(arg_str, false)?,
+                "--map-displayed-path" => self.add_path_mapping(arg_str, true)?,
+                "--include" => {
+                    let pattern = self.get_next_value(arg_str)?;
+                    self.include.push(pattern);
+                }
+                "--exclude" => {
+                    let pattern = self.get_next_value(arg_str)?;
+                    self.exclude.push(pattern);
+                }
+                "--source-root" => {
+                    let source_root = self.get_next_value(arg_str)?;
+                    self.source_roots.push(PathBuf::from(source_root));
+                }
+                "--source-rev" => {
+                    self.source_rev = Some(self.get_next_value(arg_str)?);
+                }
+                "--diff" => {
+                    self.diff = Some(PathBuf::from(self.get_next_value(arg_str)?));
+                }
+                "--diff-base" => {
+                    self.diff_base = Some(self.get_next_value(arg_str)?);
+                }
+                "--baseline" => {
+                    self.baseline = Some(PathBuf::from(self.get_next_value(arg_str)?));
+                }
+                "--fail-under-lines" => {
+                    self.fail_under_lines = Some(self.get_next_percentage(arg_str)?);
+                }
+                "--fail-under-functions" => {
+                    self.fail_under_functions = Some(self.get_next_percentage(arg_str)?);
+                }
+                "--fail-under-branches" => {
+                    self.fail_under_branches = Some(self.get_next_percentage(arg_str)?);
+                }
+                "--tolerance" if self.ratchet_file.is_some() => {
+                    self.ratchet_options.tolerance = self.get_next_percentage(arg_str)?;
+                }
+                "--update" if self.ratchet_file.is_some() => self.ratchet_options.update = true,
                 _ => return Err(format!("Unknown argument: {}", arg)),
             }
         }
@@ -116,11 +205,42 @@ impl CliConfigParser {
             inputs: self.inputs,
             output,
             reporter: self.reporter.unwrap_or_default(),
+            keep_going: self.keep_going,
+            path_mappings: self.path_mappings,
+            include: self.include,
+            exclude: self.exclude,
+            exclusion_markers: ExclusionMarkers {
+                enabled: !self.no_markers,
+                ..Default::default()
+            },
+            source_roots: self.source_roots,
+            source_rev: self.source_rev,
+            diff: self.diff,
+            diff_base: self.diff_base,
+            baseline: self.baseline,
+            fail_under_lines: self.fail_under_lines,
+            fail_under_functions: self.fail_under_functions,
+            fail_under_branches: self.fail_under_branches,
+            thresholds: Vec::new(),
         })
     }
 
-    pub fn build(self) -> Result<CliCommand, String> {
+    /// Nothing is rendered when checking the coverage, so there is no output
+    fn build_check_config(mut self) -> Result<Config, String> {
+        if self.output.is_some() || self.reporter.is_some() {
+            return Err(format!(
+                "Arguments --output and --reporter are not allowed with {} command",
+                self.command.unwrap_or_default()
+            ));
+        }
+        self.output = Some(PathBuf::new());
+        self.build_config()
+    }
+
+    pub fn build(mut self) -> Result<CliCommand, String> {
         let config_file = self.config_file.clone();
+        let merged_report = self.merged_report.clone();
+        let ratchet_file = self.ratchet_file.clone();
 
         match self.command.as_deref() {
             Some("report") => self.build_config().map(CliCommand::Report),
@@ -132,6 +252,16 @@ impl CliConfigParser {
                 .build_config()
                 .map(|config| CliCommand::ToFile(config_file.unwrap(), config))
                 .map_err(|e| format!("Argument --to-file is required: {}", e)),
+            Some("merge") => self
+                .build_config()
+                .map(|config| CliCommand::Merge(merged_report.unwrap(), config)),
+            Some("check") => self.build_check_config().map(CliCommand::Check),
+            Some("ratchet") => {
+                let ratchet_options = std::mem::take(&mut self.ratchet_options);
+                self.build_check_config().map(|config| {
+                    CliCommand::Ratchet(ratchet_file.unwrap(), ratchet_options, config)
+                })
+            }
             Some("help") => Ok(CliCommand::Help(self.help.unwrap_or("".to_string()))),
             _ => Err("No command provided".to_string()),
         }
@@ -139,11 +269,22 @@ impl CliConfigParser {
 
     fn get_next_value(&mut self, arg_name: &str) -> Result<String, String> {
         match self.next() {
-            Some(value) if !value.starts_with("-") => Ok(value.clone()),
+            Some(value) if !value.starts_with("-") || value == STDIN_PATH => Ok(value.clone()),
             _ => Err(format!("Argument {} requires a value", arg_name)),
         }
     }
 
+    fn get_next_percentage(&mut self, arg_name: &str) -> Result<f32, String> {
+        let value = self.get_next_value(arg_name)?;
+        match value.parse::<f32>() {
+            Ok(percentage) if (0. ..=100.).contains(&percentage) => Ok(percentage),
+            _ => Err(format!(
+                "Argument {} requires a percentage between 0 and 100",
+                arg_name
+            )),
+        }
+    }
+
     fn extract_input_args(&mut self, arg_name: &str) -> Result<Input, String> {
         let arg1 = self.get_next_value(arg_name)?;
         let arg2 = self.get_next_value(arg_name);
@@ -169,15 +310,28 @@ impl CliConfigParser {
             name: Some(arg1.to_string()),
             prefix: Some(PathBuf::from(arg2.unwrap())),
             path: PathBuf::from(arg3.unwrap()),
+            ..Default::default()
         })
     }
 
     fn add_input(&mut self, arg_name: &str) -> Result<(), String> {
         let input = self.extract_input_args(arg_name)?;
+        let is_stdin = |input: &Input| input.path == std::path::Path::new(STDIN_PATH);
+        if is_stdin(&input) && self.inputs.iter().any(is_stdin) {
+            return Err("Only one input can be read from the standard input".to_string());
+        }
         self.inputs.push(input);
         Ok(())
     }
 
+    fn add_path_mapping(&mut self, arg_name: &str, display: bool) -> Result<(), String> {
+        let from = self.get_next_value(arg_name)?;
+        let to = self.get_next_value(arg_name)?;
+        self.path_mappings
+            .push(PathMapping::new(from.into(), to.into(), display));
+        Ok(())
+    }
+
     fn set_name(&mut self, arg_name: &str) -> Result<(), String> {
         let name = self.get_next_value(arg_name)?;
         if self.name.is_some() {
@@ -316,6 +470,7 @@ mod test {
                 name: "Test report".to_string(),
                 inputs: vec![Input::from_path(PathBuf::from("~/test.lcov"))],
                 reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
@@ -335,10 +490,39 @@ mod test {
                     Input::from_path(PathBuf::from("~/test2.lcov"))
                 ],
                 reporter: Reporter::default(),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_specifying_dash_as_input_path_it_shall_be_read_from_stdin() {
+        assert_eq!(
+            parse("report --input Lib - -o output")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                inputs: vec![Input::from_name_and_path(
+                    "Lib".to_string(),
+                    PathBuf::from(STDIN_PATH)
+                )],
+                reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
 
+    #[test]
+    fn when_specifying_stdin_twice_as_input_it_shall_return_error() {
+        assert_eq!(
+            parse("report --input - --input -").unwrap_err(),
+            "Only one input can be read from the standard input"
+        );
+    }
+
     #[test]
     fn when_specifying_input_without_value_it_shall_return_error() {
         assert_eq!(
@@ -370,6 +554,7 @@ mod test {
                     PathBuf::from("~/test.lcov")
                 )],
                 reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
@@ -390,6 +575,7 @@ mod test {
                     PathBuf::from("~/test.lcov")
                 )],
                 reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
@@ -414,6 +600,7 @@ mod test {
                     )
                 ],
                 reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
@@ -437,6 +624,7 @@ mod test {
                     )
                 ],
                 reporter: Reporter::default(),
+                ..Default::default()
             })
         );
     }
@@ -500,6 +688,257 @@ mod test {
         );
     }
 
+    #[test]
+    fn when_running_the_merge_command_config_shall_be_passed_along_merged_report_path() {
+        assert_eq!(
+            parse("merge merged.info -i unit.info -i e2e.info -o output")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Merge(
+                PathBuf::from("merged.info"),
+                Config {
+                    output: PathBuf::from("output"),
+                    name: "Test report".to_string(),
+                    inputs: vec![
+                        Input::from_path(PathBuf::from("unit.info")),
+                        Input::from_path(PathBuf::from("e2e.info"))
+                    ],
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn when_providing_keep_going_it_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --keep-going")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                keep_going: true,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_no_markers_exclusion_markers_shall_be_disabled_in_the_config() {
+        assert_eq!(
+            parse("report --output output --no-markers")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                exclusion_markers: ExclusionMarkers {
+                    enabled: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_path_mappings_they_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --map-path /workspace /home/me --map-displayed-path /build build")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                path_mappings: vec![
+                    PathMapping::new("/workspace".into(), "/home/me".into(), false),
+                    PathMapping::new("/build".into(), "build".into(), true),
+                ],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_path_mapping_without_destination_it_shall_return_error() {
+        assert_eq!(
+            parse("report --output output --map-path /workspace").unwrap_err(),
+            "Argument --map-path requires a value"
+        );
+    }
+
+    #[test]
+    fn when_providing_file_filters_they_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --include **/src/** --exclude **/vendor/** --exclude *.pb.go")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                include: vec!["**/src/**".to_string()],
+                exclude: vec!["**/vendor/**".to_string(), "*.pb.go".to_string()],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_source_roots_they_shall_be_set_in_the_config_in_order() {
+        assert_eq!(
+            parse("report --output output --source-root /home/me/project --source-root .")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                source_roots: vec![PathBuf::from("/home/me/project"), PathBuf::from(".")],
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_a_source_rev_it_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --source-rev 1a2b3c4")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                source_rev: Some("1a2b3c4".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_a_diff_it_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --diff changes.diff --diff-base main...HEAD")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                diff: Some(PathBuf::from("changes.diff")),
+                diff_base: Some("main...HEAD".to_string()),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_a_baseline_it_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --baseline main.lcov")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                baseline: Some(PathBuf::from("main.lcov")),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_thresholds_they_shall_be_set_in_the_config() {
+        assert_eq!(
+            parse("report --output output --fail-under-lines 80 --fail-under-branches 62.5")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Report(Config {
+                output: PathBuf::from("output"),
+                name: "Test report".to_string(),
+                fail_under_lines: Some(80.),
+                fail_under_branches: Some(62.5),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_providing_a_threshold_out_of_bounds_it_shall_return_error() {
+        assert_eq!(
+            parse("report --output output --fail-under-functions 120"),
+            Err(
+                "Argument --fail-under-functions requires a percentage between 0 and 100"
+                    .to_string()
+            )
+        );
+        assert!(parse("report --output output --fail-under-functions ninety").is_err());
+    }
+
+    #[test]
+    fn when_running_the_check_command_no_output_shall_be_required() {
+        assert_eq!(
+            parse("check --input test.lcov --fail-under-lines 80")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Check(Config {
+                name: "Test report".to_string(),
+                inputs: vec![Input::from_path(PathBuf::from("test.lcov"))],
+                fail_under_lines: Some(80.),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn when_running_the_check_command_with_an_output_it_shall_return_error() {
+        assert_eq!(
+            parse("check --input test.lcov --output output")
+                .unwrap()
+                .build(),
+            Err("Arguments --output and --reporter are not allowed with check command".to_string())
+        );
+    }
+
+    #[test]
+    fn when_running_the_ratchet_command_its_options_shall_be_passed_along_its_file() {
+        assert_eq!(
+            parse("ratchet ratchet.toml --input test.lcov --tolerance 0.5 --update")
+                .unwrap()
+                .build()
+                .unwrap(),
+            CliCommand::Ratchet(
+                PathBuf::from("ratchet.toml"),
+                RatchetOptions {
+                    tolerance: 0.5,
+                    update: true,
+                },
+                Config {
+                    name: "Test report".to_string(),
+                    inputs: vec![Input::from_path(PathBuf::from("test.lcov"))],
+                    ..Default::default()
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn when_providing_ratchet_options_to_another_command_it_shall_return_error() {
+        assert_eq!(
+            parse("check --input test.lcov --update"),
+            Err("Unknown argument: --update".to_string())
+        );
+    }
+
     #[test]
     fn when_running_from_file_command_it_shall_return_the_config_path() {
         assert_eq!(
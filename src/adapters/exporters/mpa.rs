@@ -1,9 +1,6 @@
 use std::{error::Error, path::PathBuf};
 
-use crate::{
-    core::{Exporter, FileSystem, Renderer, TestedContainer, TestedFile, WithPath},
-    file_provider::LocalFileLinesProvider,
-};
+use crate::core::{Exporter, FileSystem, Renderer, TestedContainer, TestedFile, WithPath};
 
 pub struct MpaExporter<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer> {
     renderer: TRenderer,
@@ -28,12 +25,21 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
         }
     }
 
+    /// The page of a file whose source can't be read is still rendered, the
+    /// source is added to the missing ones
     fn render_file(
         &self,
         root: &impl WithPath,
         file: &impl TestedFile,
+        missing_sources: &mut Vec<PathBuf>,
     ) -> Result<(), Box<dyn Error>> {
-        let lines_provider = LocalFileLinesProvider::new(file.get_original_file_path());
+        let rendered = self
+            .renderer
+            .render_file_coverage_details(root, file, &file.get_source());
+        if rendered.is_source_missing {
+            missing_sources.push(file.get_original_file_path());
+        }
+
         let mut target_path = self.output_path_root.join(file.get_path());
 
         let extension = target_path.extension().unwrap_or_default();
@@ -42,12 +48,8 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
         self.file_system
             .create_dir_all(target_path.parent().unwrap())?;
 
-        self.file_system.write_all(
-            &target_path,
-            &self
-                .renderer
-                .render_file_coverage_details(root, file, &lines_provider),
-        )?;
+        self.file_system
+            .write_all(&target_path, &rendered.content)?;
 
         Ok(())
     }
@@ -56,6 +58,7 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
         &self,
         root: &impl WithPath,
         module: &impl TestedContainer,
+        missing_sources: &mut Vec<PathBuf>,
     ) -> Result<(), Box<dyn Error>> {
         let relative_path_root_to_module = module.get_path();
 
@@ -68,11 +71,11 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
         )?;
 
         for child in module.get_container_children() {
-            self.render_module(root, child)?;
+            self.render_module(root, child, missing_sources)?;
         }
 
         for file in module.get_code_file_children() {
-            self.render_file(root, file)?;
+            self.render_file(root, file, missing_sources)?;
         }
 
         Ok(())
@@ -82,11 +85,16 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
 impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer> Exporter
     for MpaExporter<'a, TRenderer, TFileSystem, TRoot>
 {
-    fn render_root(self) -> () {
-        self.render_module(&self.root, &self.root).expect(&format!(
-            "Failed to render root to {}:",
-            self.output_path_root.display()
-        ));
+    fn render_root(self) -> Vec<PathBuf> {
+        let mut missing_sources = vec![];
+        self.render_module(&self.root, &self.root, &mut missing_sources)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "Failed to render root to {}: {:?}",
+                    self.output_path_root.display(),
+                    err
+                )
+            });
 
         let required_resources = self.renderer.get_required_resources(&self.root);
         self.file_system
@@ -98,6 +106,7 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer> E
                 .write_all(&target_path, resource_content)
                 .unwrap();
         }
+        missing_sources
     }
 }
 
@@ -105,7 +114,10 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer> E
 mod test {
 
     use crate::{
-        adapters::renderers::mock_renderer::MockRenderer,
+        adapters::{
+            exporters::mpa_links::MpaLinksComputer,
+            renderers::{html_light_renderer::HtmlLightRenderer, mock_renderer::MockRenderer},
+        },
         aggregation::{fixtures::AggregatedFixtures, tested_root::TestedRoot},
         core::MockFileSystem,
     };
@@ -145,7 +157,7 @@ mod test {
         expect_write_all!(fs, "target/_resources/resource.svg", "<svg>...</svg>");
 
         let exporter = MpaExporter::new(MockRenderer, empty_report, &output_path, &fs);
-        exporter.render_root();
+        assert!(exporter.render_root().is_empty());
     }
 
     #[test]
@@ -161,7 +173,7 @@ mod test {
         expect_write_all!(fs, "target/_resources/resource.svg", "<svg>...</svg>");
 
         let exporter = MpaExporter::new(MockRenderer, report, &output_path, &fs);
-        exporter.render_root();
+        assert_eq!(exporter.render_root(), vec![PathBuf::from("main.cpp")]);
     }
 
     #[test]
@@ -184,6 +196,35 @@ mod test {
         expect_write_all!(fs, "target/_resources/resource.svg", "<svg>...</svg>");
 
         let exporter = MpaExporter::new(MockRenderer, report, &output_path, &fs);
-        exporter.render_root();
+        assert_eq!(
+            exporter.render_root(),
+            vec![
+                PathBuf::from("module/nested.cpp"),
+                PathBuf::from("main.cpp")
+            ]
+        );
+    }
+
+    #[test]
+    fn when_a_source_is_missing_its_page_shall_warn_about_it_and_it_shall_be_returned() {
+        let report = AggregatedFixtures::get_top_level_file_report_no_line();
+        let output_path = PathBuf::from("target");
+
+        let mut fs = MockFileSystem::new();
+        fs.expect_create_dir_all().returning(|_| Ok(()));
+        fs.expect_write_all()
+            .times(1)
+            .withf(|path, content| {
+                path == Path::new("target/main.cpp.html")
+                    && content.contains(r#"<div class="missing-source">"#)
+            })
+            .returning(|_, _| Ok(()));
+        fs.expect_write_all()
+            .withf(|path, _| path != Path::new("target/main.cpp.html"))
+            .returning(|_, _| Ok(()));
+
+        let renderer = HtmlLightRenderer::new(MpaLinksComputer);
+        let exporter = MpaExporter::new(renderer, report, &output_path, &fs);
+        assert_eq!(exporter.render_root(), vec![PathBuf::from("main.cpp")]);
     }
 }
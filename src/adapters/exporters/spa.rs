@@ -45,11 +45,13 @@ impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer>
 impl<'a, TRenderer: Renderer, TFileSystem: FileSystem, TRoot: TestedContainer> Exporter
     for SpaExporter<'a, TRenderer, TFileSystem, TRoot>
 {
-    fn render_root(self) -> () {
+    /// Sources are not rendered, none of them is missing
+    fn render_root(self) -> Vec<PathBuf> {
         self.render().expect(&format!(
             "Failed to render root to {}:",
             self.output_path_root.display()
         ));
+        vec![]
     }
 }
 
@@ -45,13 +45,19 @@ impl<'a> ToHtml for CoverageChip<'a> {
 
 pub fn render_aggregated_coverage_chips(
     coverage: &AggregatedCoverage,
-) -> impl Iterator<Item = CoverageChip> {
-    vec![
+) -> impl Iterator<Item = CoverageChip<'_>> {
+    let mut chips = vec![
         CoverageChip::new("Lines", &coverage.lines),
         CoverageChip::new("Functions", &coverage.functions),
         CoverageChip::new("Branches", &coverage.branches),
-    ]
-    .into_iter()
+    ];
+    if coverage.has_regions() {
+        chips.push(CoverageChip::new("Regions", &coverage.regions));
+    }
+    if let Some(changed_lines) = &coverage.changed_lines {
+        chips.push(CoverageChip::new("Changed lines", changed_lines));
+    }
+    chips.into_iter()
 }
 
 #[cfg(test)]
@@ -76,6 +82,7 @@ mod tests {
             lines: AggregatedCoverageCounters::new(10, 5),
             functions: AggregatedCoverageCounters::new(10, 4),
             branches: AggregatedCoverageCounters::new(10, 3),
+            ..Default::default()
         };
 
         let chips = render_aggregated_coverage_chips(&coverage).collect::<Vec<_>>();
@@ -89,4 +96,30 @@ mod tests {
         assert!(html[2].contains("Branches 3/10"));
         assert!(html[2].contains("30.00%"));
     }
+
+    #[test]
+    fn when_rendering_chips_of_coverage_with_regions_it_shall_add_a_regions_chip() {
+        let coverage = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(10, 5),
+            regions: AggregatedCoverageCounters::new(4, 1),
+            ..Default::default()
+        };
+
+        let chips = render_aggregated_coverage_chips(&coverage).collect::<Vec<_>>();
+        assert_eq!(chips.len(), 4);
+        assert!(chips[3].to_html().contains("Regions 1/4"));
+    }
+
+    #[test]
+    fn when_rendering_chips_of_the_coverage_of_a_diff_it_shall_add_a_changed_lines_chip() {
+        let coverage = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(10, 5),
+            changed_lines: Some(AggregatedCoverageCounters::new(0, 0)),
+            ..Default::default()
+        };
+
+        let chips = render_aggregated_coverage_chips(&coverage).collect::<Vec<_>>();
+        assert_eq!(chips.len(), 4);
+        assert!(chips[3].to_html().contains("Changed lines 0/0"));
+    }
 }
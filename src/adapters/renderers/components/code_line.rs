@@ -1,43 +1,125 @@
+use htmlescape::encode_minimal;
+
 use crate::{
-    core::TestedFile,
+    core::{BranchCoverage, TestedFile},
     html::components::{Div, Pre, Row, Table, Text, ToHtml},
 };
 
+/// Number of branches taken on a line, expandable into the taken count of
+/// each of them
+struct LineBranches {
+    branches: Vec<BranchCoverage>,
+}
+impl LineBranches {
+    fn render_branch(branch: &BranchCoverage) -> String {
+        let taken = match branch.taken {
+            None => String::from("not executed"),
+            Some(0) => String::from("not taken"),
+            Some(taken) => format!("taken {}", taken),
+        };
+        format!(
+            "<li>Block {}, branch {}: {}</li>",
+            branch.block,
+            branch.branch,
+            encode_minimal(&taken)
+        )
+    }
+}
+impl ToHtml for LineBranches {
+    fn to_html(&self) -> String {
+        if self.branches.is_empty() {
+            return String::new();
+        }
+
+        let taken_count = self.branches.iter().filter(|b| b.is_covered()).count();
+        let branches: String = self.branches.iter().map(Self::render_branch).collect();
+        format!(
+            r#"<details class="line-branches"><summary>{}/{} taken</summary><ul>{}</ul></details>"#,
+            taken_count,
+            self.branches.len(),
+            branches
+        )
+    }
+}
+
 pub struct CodeLines<'a, TFile: TestedFile> {
     file: &'a TFile,
     lines: Vec<String>,
+    line_numbers: Vec<u32>,
 }
 impl<'a, TFile: TestedFile> CodeLines<'a, TFile> {
     pub fn new(file: &'a TFile, lines: Vec<String>) -> Self {
-        Self { file, lines }
+        let line_numbers = (1..=lines.len() as u32).collect();
+        Self {
+            file,
+            lines,
+            line_numbers,
+        }
+    }
+
+    /// Without the source, only the lines of the report are listed, with no
+    /// code
+    pub fn without_source(file: &'a TFile) -> Self {
+        Self {
+            file,
+            lines: vec![],
+            line_numbers: file.get_tested_lines(),
+        }
     }
 
-    fn render_line(&self, line_number: usize) -> Row {
-        let coverage = self.file.get_line_coverage(line_number as u32);
+    /// Lines of the source are numbered from 1, like in the report, and can be
+    /// linked to with their `L<number>` anchor
+    fn render_line(&self, line_number: u32) -> Row<'_> {
+        let coverage = self.file.get_line_coverage(line_number);
         let empty_line = String::new();
-        let line = self.lines.get(line_number).unwrap_or(&empty_line);
+        let line = (line_number as usize)
+            .checked_sub(1)
+            .and_then(|line_index| self.lines.get(line_index))
+            .unwrap_or(&empty_line);
+
+        let branches = self.file.get_line_branches(line_number);
+        let partially_covered =
+            branches.iter().any(|b| b.is_covered()) && branches.iter().any(|b| !b.is_covered());
 
         let class = match coverage {
+            _ if self.file.is_line_excluded(line_number) => "line-excluded",
+            Some(cov) if cov > 0 && partially_covered => "line-partially-covered",
             Some(cov) if cov > 0 => "line-covered",
             Some(_) => "line-not-covered",
             None => "line-not-tested",
         };
 
-        Row::new()
-            .with_class(class)
-            .with_cell(Text::new(&(line_number + 1).to_string()))
+        let row = Row::new().with_class(class);
+        let row = match self.file.is_line_changed(line_number) {
+            true => row.with_class("line-changed"),
+            false => row,
+        };
+        let row = row
+            .with_id(&format!("L{}", line_number))
+            .with_cell(Text::new(&line_number.to_string()))
             .with_cell(Text::new(
                 &coverage.map(|c| c.to_string()).unwrap_or_default(),
             ))
-            .with_cell(Pre::new(&line))
+            .with_cell(Pre::new(line));
+
+        // Branches of the line, when the file has some
+        let row = match self.file.get_aggregated_coverage().branches.count {
+            0 => row,
+            _ => row.with_cell(LineBranches { branches }),
+        };
+
+        // Named tests hitting the line, when the report has some
+        match self.file.get_aggregated_coverage().tests.is_empty() {
+            true => row,
+            false => row.with_cell(Text::new(&self.file.get_line_tests(line_number).join(", "))),
+        }
     }
 
-    fn render_lines(&self) -> Div {
+    fn render_lines(&self) -> Div<'_> {
         let rows = self
-            .lines
+            .line_numbers
             .iter()
-            .enumerate()
-            .map(|(i, _)| self.render_line(i));
+            .map(|line_number| self.render_line(*line_number));
 
         Div::new()
             .with_class("lines")
@@ -56,17 +138,23 @@ mod test {
 
     use super::*;
     use crate::{
-        aggregation::tested_file::TestedCodeFile, assert_html_eq, test_utils::builders::InsertLine,
+        aggregation::{exclusions::Exclusions, tested_file::TestedCodeFile},
+        assert_html_eq,
+        input::config::ExclusionMarkers,
+        test_utils::builders::InsertLine,
+    };
+    use lcov::report::section::{
+        branch::{Key as BranchKey, Value as BranchValue},
+        Key as SectionKey, Value as SectionValue,
     };
-    use lcov::report::section::{Key as SectionKey, Value as SectionValue};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_render_lines() {
         let section = SectionValue::default()
             .insert_line(1, 3)
             .insert_line(2, 1)
-            .insert_line(3, 0)
-            .insert_line(4, 2);
+            .insert_line(3, 0);
         let key = SectionKey {
             source_file: PathBuf::from("file.cpp"),
             test_name: String::from(""),
@@ -86,10 +174,177 @@ mod test {
             lines.to_html(),
             r#"<div class="lines">"#,
             "<table>",
-            r#"<tr class="line-not-tested"><td>1</td><td></td><td><pre>line 1</pre></td></tr>"#,
-            r#"<tr class="line-covered"><td>2</td><td>3</td><td><pre>line 2</pre></td></tr>"#,
-            r#"<tr class="line-covered"><td>3</td><td>1</td><td><pre>line 3</pre></td></tr>"#,
-            r#"<tr class="line-not-covered"><td>4</td><td>0</td><td><pre>line 4</pre></td></tr>"#,
+            r#"<tr class="line-covered" id="L1"><td>1</td><td>3</td><td><pre>line 1</pre></td></tr>"#,
+            r#"<tr class="line-covered" id="L2"><td>2</td><td>1</td><td><pre>line 2</pre></td></tr>"#,
+            r#"<tr class="line-not-covered" id="L3"><td>3</td><td>0</td><td><pre>line 3</pre></td></tr>"#,
+            r#"<tr class="line-not-tested" id="L4"><td>4</td><td></td><td><pre>line 4</pre></td></tr>"#,
+            "</table>",
+            "</div>"
+        );
+    }
+
+    #[test]
+    fn when_a_line_is_excluded_it_shall_have_the_excluded_style() {
+        let source = vec![
+            String::from("int a = 0;"),
+            String::from("abort(); // LCOV_EXCL_LINE"),
+        ];
+        let mut section = SectionValue::default().insert_line(1, 1).insert_line(2, 0);
+        let exclusions = Exclusions::scan(&source, &ExclusionMarkers::default());
+        exclusions.apply(&mut section);
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "").with_exclusions(exclusions);
+
+        assert_html_eq!(
+            CodeLines::new(&file, source).to_html(),
+            r#"<div class="lines">"#,
+            "<table>",
+            r#"<tr class="line-covered" id="L1"><td>1</td><td>1</td><td><pre>int a = 0;</pre></td></tr>"#,
+            r#"<tr class="line-excluded" id="L2"><td>2</td><td></td><td><pre>abort(); // LCOV_EXCL_LINE</pre></td></tr>"#,
+            "</table>",
+            "</div>"
+        );
+    }
+
+    #[test]
+    fn when_the_report_has_tests_each_line_shall_list_the_tests_hitting_it() {
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let section = SectionValue::default()
+            .insert_line(1, 2)
+            .insert_line(2, 1)
+            .insert_line(3, 0);
+        let tests = BTreeMap::from([
+            (
+                String::from("e2e"),
+                SectionValue::default().insert_line(1, 1).insert_line(2, 0),
+            ),
+            (
+                String::from("unit"),
+                SectionValue::default().insert_line(1, 1).insert_line(2, 1),
+            ),
+        ]);
+        let file = TestedCodeFile::from_section(key, section, "", "").with_tests(tests);
+        let source = vec![
+            String::from("line 1"),
+            String::from("line 2"),
+            String::from("line 3"),
+        ];
+
+        assert_html_eq!(
+            CodeLines::new(&file, source).to_html(),
+            r#"<div class="lines">"#,
+            "<table>",
+            r#"<tr class="line-covered" id="L1"><td>1</td><td>2</td><td><pre>line 1</pre></td><td>e2e, unit</td></tr>"#,
+            r#"<tr class="line-covered" id="L2"><td>2</td><td>1</td><td><pre>line 2</pre></td><td>unit</td></tr>"#,
+            r#"<tr class="line-not-covered" id="L3"><td>3</td><td>0</td><td><pre>line 3</pre></td><td></td></tr>"#,
+            "</table>",
+            "</div>"
+        );
+    }
+
+    #[test]
+    fn when_the_file_has_branches_each_line_shall_show_the_taken_ones() {
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let mut section = SectionValue::default()
+            .insert_line(1, 2)
+            .insert_line(2, 2)
+            .insert_line(3, 1);
+        for (line, block, branch, taken) in [
+            (1, 0, 0, Some(1)),
+            (1, 0, 1, Some(0)),
+            (1, 1, 0, None),
+            (2, 0, 0, Some(2)),
+        ] {
+            section.branches.insert(
+                BranchKey {
+                    line,
+                    block,
+                    branch,
+                },
+                BranchValue { taken },
+            );
+        }
+        let file = TestedCodeFile::from_section(key, section, "", "");
+        let source = vec![
+            String::from("if (a || b)"),
+            String::from("if (c)"),
+            String::from("d();"),
+        ];
+
+        assert_html_eq!(
+            CodeLines::new(&file, source).to_html(),
+            r#"<div class="lines">"#,
+            "<table>",
+            r#"<tr class="line-partially-covered" id="L1"><td>1</td><td>2</td><td><pre>if (a || b)</pre></td><td>"#,
+            r#"<details class="line-branches"><summary>1/3 taken</summary><ul>"#,
+            "<li>Block 0, branch 0: taken 1</li>",
+            "<li>Block 0, branch 1: not taken</li>",
+            "<li>Block 1, branch 0: not executed</li>",
+            "</ul></details></td></tr>",
+            r#"<tr class="line-covered" id="L2"><td>2</td><td>2</td><td><pre>if (c)</pre></td><td>"#,
+            r#"<details class="line-branches"><summary>1/1 taken</summary><ul>"#,
+            "<li>Block 0, branch 0: taken 2</li>",
+            "</ul></details></td></tr>",
+            r#"<tr class="line-covered" id="L3"><td>3</td><td>1</td><td><pre>d();</pre></td><td></td></tr>"#,
+            "</table>",
+            "</div>"
+        );
+    }
+
+    #[test]
+    fn when_the_source_is_missing_only_the_lines_of_the_report_shall_be_listed() {
+        let section = SectionValue::default().insert_line(2, 1).insert_line(5, 0);
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "");
+
+        assert_html_eq!(
+            CodeLines::without_source(&file).to_html(),
+            r#"<div class="lines">"#,
+            "<table>",
+            r#"<tr class="line-covered" id="L2"><td>2</td><td>1</td><td><pre></pre></td></tr>"#,
+            r#"<tr class="line-not-covered" id="L5"><td>5</td><td>0</td><td><pre></pre></td></tr>"#,
+            "</table>",
+            "</div>"
+        );
+    }
+
+    #[test]
+    fn when_lines_are_changed_by_a_diff_they_shall_be_highlighted() {
+        let section = SectionValue::default().insert_line(1, 1).insert_line(2, 0);
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "")
+            .with_changed_lines([2, 3].into_iter().collect());
+
+        assert_html_eq!(
+            CodeLines::new(
+                &file,
+                vec![
+                    String::from("a();"),
+                    String::from("b();"),
+                    String::from("// c")
+                ]
+            )
+            .to_html(),
+            r#"<div class="lines">"#,
+            "<table>",
+            r#"<tr class="line-covered" id="L1"><td>1</td><td>1</td><td><pre>a();</pre></td></tr>"#,
+            r#"<tr class="line-not-covered line-changed" id="L2"><td>2</td><td>0</td><td><pre>b();</pre></td></tr>"#,
+            r#"<tr class="line-not-tested line-changed" id="L3"><td>3</td><td></td><td><pre>// c</pre></td></tr>"#,
             "</table>",
             "</div>"
         );
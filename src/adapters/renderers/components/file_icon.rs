@@ -64,15 +64,36 @@ mod tests {
         fn get_aggregated_coverage(&self) -> &crate::core::AggregatedCoverage {
             unimplemented!()
         }
-        fn get_functions(&self) -> impl Iterator<Item = (String, u64)> {
+        fn get_functions(&self) -> impl Iterator<Item = crate::core::FunctionCoverage> {
             return [].iter().cloned();
         }
         fn get_line_coverage(&self, _line: u32) -> Option<u64> {
             unimplemented!()
         }
+        fn get_tested_lines(&self) -> Vec<u32> {
+            unimplemented!()
+        }
+        fn is_line_excluded(&self, _line: u32) -> bool {
+            unimplemented!()
+        }
+        fn is_line_changed(&self, _line: u32) -> bool {
+            unimplemented!()
+        }
+        fn get_line_tests(&self, _line: u32) -> Vec<&str> {
+            unimplemented!()
+        }
+        fn get_line_branches(&self, _line: u32) -> Vec<crate::core::BranchCoverage> {
+            unimplemented!()
+        }
         fn get_original_file_path(&self) -> std::path::PathBuf {
             unimplemented!()
         }
+        fn get_source_root(&self) -> Option<&std::path::Path> {
+            unimplemented!()
+        }
+        fn get_source(&self) -> impl crate::core::FileLinesProvider + '_ {
+            crate::file_provider::LocalFileLinesProvider::new(self.path.clone().into())
+        }
     }
 
     #[test]
@@ -1,8 +1,44 @@
+use std::cmp::Ordering;
+
 use crate::{
-    core::{LinksComputer, TestedFile},
-    html::components::{Div, Img, Text, ToHtml},
+    core::{
+        AggregatedCoverageCounters, FunctionCoverage, FunctionInstance, LinksComputer, TestedFile,
+    },
+    html::components::{Div, Gauge, Img, Link, Text, ToHtml},
 };
 
+/// Show the functions in the selected order, hiding the other lists
+const ON_SORT_CHANGE: &str = "document.querySelectorAll('.functions-order').forEach(e => e.hidden = e.dataset.sort !== this.value)";
+
+/// Instances of a generic function or template, listed under their base name
+struct FunctionInstances<'a> {
+    name: Box<dyn ToHtml>,
+    instances: &'a [FunctionInstance],
+}
+impl<'a> ToHtml for FunctionInstances<'a> {
+    fn to_html(&self) -> String {
+        let instances: String = self
+            .instances
+            .iter()
+            .map(|instance| {
+                let instance_div =
+                    Div::new().with_text(&format!("{}: {} calls", instance.name, instance.count));
+                match &instance.mangled_name {
+                    Some(mangled_name) => instance_div.with_title(mangled_name),
+                    None => instance_div,
+                }
+                .to_html()
+            })
+            .collect();
+        format!(
+            r#"<details class="function-instances"><summary>{} ({} instances)</summary>{}</details>"#,
+            self.name.to_html(),
+            self.instances.len(),
+            instances
+        )
+    }
+}
+
 pub struct FunctionDefs<'a, TLinksComputer: LinksComputer, TFile: TestedFile> {
     file: &'a TFile,
     links_computer: &'a TLinksComputer,
@@ -42,29 +78,123 @@ impl<'a, TLinksComputer: LinksComputer, TFile: TestedFile> FunctionDefs<'a, TLin
         Img::new(&self.get_img_src(count), "Function coverage")
     }
 
-    fn render_function(&self, name: &str, count: u64) -> Div {
+    /// The name links to the definition of the function, when its start line
+    /// is known. The mangled name is shown as a tooltip.
+    fn render_name<'b>(function: &'b FunctionCoverage) -> Div<'b> {
+        let name: Box<dyn ToHtml> = match function.start_line {
+            Some(line) => Box::new(Link::from_text(&format!("#L{}", line), &function.name)),
+            None => Box::new(Text::new(&function.name)),
+        };
+        let div = Div::new().with_class("function-name");
+        let div = match &function.mangled_name {
+            Some(mangled_name) => div.with_title(mangled_name),
+            None => div,
+        };
+        match function.instances.is_empty() {
+            true => div.with_child(name),
+            false => div.with_child(FunctionInstances {
+                name,
+                instances: &function.instances,
+            }),
+        }
+    }
+
+    fn render_lines<'b>(function: &FunctionCoverage) -> Div<'b> {
+        let lines = match (function.start_line, function.end_line) {
+            (Some(start), Some(end)) if end > start => format!("Lines {}-{}", start, end),
+            (Some(start), _) => format!("Line {}", start),
+            (None, _) => String::new(),
+        };
         Div::new()
+            .with_class("function-lines")
+            .with_child(Text::new(&lines))
+    }
+
+    fn render_gauge(name: &str, counters: &AggregatedCoverageCounters) -> Gauge {
+        Gauge::new(
+            counters.percentage(),
+            &format!("{} {}/{}", name, counters.covered_count, counters.count),
+            None,
+        )
+    }
+
+    /// Gauges of the page, at a smaller size
+    fn render_coverage(function: &FunctionCoverage) -> Div<'_> {
+        let coverage = Div::new()
+            .with_class("function-coverage")
+            .with_child(Self::render_gauge("Lines", &function.lines));
+        match function.branches.count {
+            0 => coverage,
+            _ => coverage.with_child(Self::render_gauge("Branches", &function.branches)),
+        }
+    }
+
+    /// The lines and branches of a function are known from its start line only
+    fn render_function<'b>(&self, function: &'b FunctionCoverage) -> Div<'b> {
+        let count = function.count;
+        let div = Div::new()
             .with_class("function")
             .with_class(Self::get_class(count))
             .with_child(self.get_img(count))
-            .with_child(
-                Div::new()
-                    .with_class("function-name")
-                    .with_child(Text::new(name)),
-            )
-            .with_child(Div::new().with_class("fill"))
-            .with_child(
-                Div::new()
-                    .with_class("function-hit")
-                    .with_child(Text::new(&format!("{} calls", count))),
+            .with_child(Self::render_name(function));
+        let div = match function.start_line {
+            Some(_) => div
+                .with_child(Self::render_lines(function))
+                .with_child(Self::render_coverage(function)),
+            None => div,
+        };
+        div.with_child(Div::new().with_class("fill")).with_child(
+            Div::new()
+                .with_class("function-hit")
+                .with_child(Text::new(&format!("{} calls", count))),
+        )
+    }
+
+    fn render_list(&self, functions: &[&FunctionCoverage]) -> String {
+        Div::new()
+            .with_class("functions")
+            .with_children(
+                functions
+                    .iter()
+                    .map(|function| self.render_function(function)),
             )
+            .to_html()
+    }
+
+    /// Functions with the worst covered lines come first, then the ones with
+    /// the worst covered branches. Functions without any line come last.
+    fn compare_coverage(a: &FunctionCoverage, b: &FunctionCoverage) -> Ordering {
+        let percentage =
+            |counters: &AggregatedCoverageCounters| counters.percentage().unwrap_or(f32::INFINITY);
+        percentage(&a.lines)
+            .total_cmp(&percentage(&b.lines))
+            .then(percentage(&a.branches).total_cmp(&percentage(&b.branches)))
     }
 
-    fn render_functions(&self) -> Div {
-        Div::new().with_class("functions").with_children(
-            self.file
-                .get_functions()
-                .map(|(name, count)| self.render_function(&name, count)),
+    /// Without start lines, the coverage of the functions is unknown, so they
+    /// can't be sorted by it
+    fn render_functions(&self) -> String {
+        let functions = self.file.get_functions().collect::<Vec<_>>();
+        let by_name = functions.iter().collect::<Vec<_>>();
+        if !functions
+            .iter()
+            .any(|function| function.start_line.is_some())
+        {
+            return self.render_list(&by_name);
+        }
+
+        let mut by_coverage = by_name.clone();
+        by_coverage.sort_by(|a, b| Self::compare_coverage(a, b));
+        format!(
+            concat!(
+                r#"<div class="function-sort"><label for="function-sort">Sort by</label>"#,
+                r#"<select id="function-sort" onchange="{}"><option value="name">Name</option><option value="coverage">Worst coverage</option></select></div>"#,
+                r#"<div class="functions-order" data-sort="name">{}</div>"#,
+                r#"<div class="functions-order" data-sort="coverage" hidden>{}</div>"#,
+            ),
+            ON_SORT_CHANGE,
+            self.render_list(&by_name),
+            self.render_list(&by_coverage)
         )
     }
 }
@@ -73,7 +203,7 @@ impl<'a, TLinksComputer: LinksComputer, TFile: TestedFile> ToHtml
     for FunctionDefs<'a, TLinksComputer, TFile>
 {
     fn to_html(&self) -> String {
-        self.render_functions().to_html()
+        self.render_functions()
     }
 }
 
@@ -83,10 +213,17 @@ mod test {
 
     use super::*;
     use crate::{
-        adapters::exporters::mpa_links::MpaLinksComputer, aggregation::tested_file::TestedCodeFile,
-        assert_html_eq, test_utils::builders::InsertFunction,
+        adapters::exporters::mpa_links::MpaLinksComputer,
+        aggregation::tested_file::TestedCodeFile,
+        assert_html_eq,
+        test_utils::builders::{InsertFunction, InsertLine},
     };
-    use lcov::report::section::{Key as SectionKey, Value as SectionValue};
+    use lcov::report::section::{
+        function::{Key as FunctionKey, Value as FunctionValue},
+        Key as SectionKey, Value as SectionValue,
+    };
+    use std::collections::BTreeMap;
+
     #[test]
     fn render_one_uncovered_function() {
         let section = SectionValue::default().insert_function("f1", 0);
@@ -149,4 +286,99 @@ mod test {
             r#"</div>"#
         );
     }
+
+    fn generate_file_with_functions(functions: &[(&str, u32, u64)]) -> TestedCodeFile {
+        let mut section = SectionValue::default()
+            .insert_line(3, 1)
+            .insert_line(4, 0)
+            .insert_line(10, 1);
+        for (name, start_line, count) in functions {
+            section.functions.insert(
+                FunctionKey {
+                    name: String::from(*name),
+                },
+                FunctionValue {
+                    start_line: Some(*start_line),
+                    count: *count,
+                },
+            );
+        }
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        TestedCodeFile::from_section(key, section, "", "")
+    }
+
+    #[test]
+    fn when_the_start_line_is_known_the_function_shall_link_to_it() {
+        let file = generate_file_with_functions(&[("f1", 3, 1), ("f2", 10, 1)])
+            .with_function_end_lines(BTreeMap::from([(String::from("f1"), 7)]));
+
+        let html = FunctionDefs::new(&file, &MpaLinksComputer).to_html();
+
+        assert!(html.contains(
+            r##"<div class="function-name"><a href="#L3">f1</a></div><div class="function-lines">Lines 3-7</div>"##
+        ));
+        assert!(html.contains(
+            r##"<div class="function-name"><a href="#L10">f2</a></div><div class="function-lines">Line 10</div>"##
+        ));
+    }
+
+    #[test]
+    fn when_the_start_line_is_known_the_function_shall_show_the_coverage_of_its_lines() {
+        let file = generate_file_with_functions(&[("f1", 3, 1)]);
+
+        let html = FunctionDefs::new(&file, &MpaLinksComputer).to_html();
+
+        assert!(html.contains(r#"<div class="function-coverage"><div class="gauge">"#));
+        assert!(
+            html.contains(r#"<span class="percent">66.67%</span></div></div><div>Lines 2/3</div>"#)
+        );
+    }
+
+    #[test]
+    fn when_sorting_by_coverage_the_worst_covered_functions_shall_come_first() {
+        let file = generate_file_with_functions(&[("f1", 3, 1), ("f2", 10, 1)]);
+        let functions = FunctionDefs::new(&file, &MpaLinksComputer).to_html();
+
+        let sorted_by_coverage = functions
+            .split(r#"data-sort="coverage" hidden>"#)
+            .nth(1)
+            .unwrap();
+        assert!(
+            sorted_by_coverage.find(">f1<").unwrap() < sorted_by_coverage.find(">f2<").unwrap()
+        );
+        let sorted_by_name = functions.split(r#"data-sort="name">"#).nth(1).unwrap();
+        assert!(sorted_by_name.find(">f2<").unwrap() > sorted_by_name.find(">f1<").unwrap());
+    }
+
+    #[test]
+    fn when_a_function_is_mangled_its_mangled_name_shall_be_a_tooltip() {
+        let file = generate_file_with_functions(&[("_Z6squarei", 3, 1)]);
+
+        let html = FunctionDefs::new(&file, &MpaLinksComputer).to_html();
+
+        assert!(html.contains(
+            r##"<div class="function-name" title="&#x5F;Z6squarei"><a href="#L3">square(int)</a></div>"##
+        ));
+    }
+
+    #[test]
+    fn when_a_template_has_several_instances_they_shall_be_listed_under_its_name() {
+        let file = generate_file_with_functions(&[
+            ("_Z3maxIiET_S0_S0_", 3, 2),
+            ("_Z3maxIlET_S0_S0_", 3, 1),
+        ]);
+
+        let html = FunctionDefs::new(&file, &MpaLinksComputer).to_html();
+
+        assert!(html.contains(concat!(
+            r##"<div class="function-name"><details class="function-instances"><summary><a href="#L3">max</a> (2 instances)</summary>"##,
+            r##"<div title="&#x5F;Z3maxIiET&#x5F;S0&#x5F;S0&#x5F;">max&lt;int&gt;(int, int): 2 calls</div>"##,
+            r##"<div title="&#x5F;Z3maxIlET&#x5F;S0&#x5F;S0&#x5F;">max&lt;long&gt;(long, long): 1 calls</div>"##,
+            r##"</details></div>"##,
+        )));
+        assert!(html.contains(r#"<div class="function-hit">3 calls</div>"#));
+    }
 }
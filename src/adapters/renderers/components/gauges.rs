@@ -25,7 +25,17 @@ impl<'a> CoverageGauges<'a> {
         )
     }
 
-    fn render_gauges(&self) -> Div {
+    fn render_gauges(&self) -> Div<'_> {
+        let regions = self
+            .coverage
+            .has_regions()
+            .then(|| Self::render_gauge(&self.coverage.regions, "Regions", false));
+        let changed_lines = self
+            .coverage
+            .changed_lines
+            .as_ref()
+            .map(|changed_lines| Self::render_gauge(changed_lines, "Changed lines", false));
+
         Div::new()
             .with_class("gauges")
             .with_child(Self::render_gauge(
@@ -43,6 +53,8 @@ impl<'a> CoverageGauges<'a> {
                 "Branches",
                 false,
             ))
+            .with_children(regions.into_iter())
+            .with_children(changed_lines.into_iter())
     }
 }
 
@@ -64,6 +76,7 @@ mod tests {
             lines: AggregatedCoverageCounters::new(10, 5),
             functions: AggregatedCoverageCounters::new(10, 4),
             branches: AggregatedCoverageCounters::new(10, 3),
+            ..Default::default()
         };
         let gauges = CoverageGauges::new(&coverage, true);
         let html = gauges.to_html();
@@ -86,4 +99,45 @@ mod tests {
             ),
         );
     }
+
+    #[test]
+    fn test_coverage_gauges_without_regions_shall_not_render_regions_gauge() {
+        let coverage = crate::core::AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(10, 5),
+            ..Default::default()
+        };
+        let html = CoverageGauges::new(&coverage, true).to_html();
+
+        assert!(!html.contains("Regions"));
+    }
+
+    #[test]
+    fn test_coverage_gauges_with_regions_shall_render_regions_gauge() {
+        let coverage = crate::core::AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(10, 5),
+            regions: AggregatedCoverageCounters::new(10, 8),
+            ..Default::default()
+        };
+        let html = CoverageGauges::new(&coverage, true).to_html();
+
+        assert!(
+            html.contains(
+                r##"<div class="gauge-c bg-8" style="transform: rotate(0.40turn)"></div><div class="gauge-data"><span class="percent">80.00%</span></div></div><div>Regions 8/10</div>"##,
+            ),
+        );
+    }
+
+    #[test]
+    fn when_reporting_the_coverage_of_a_diff_a_changed_lines_gauge_shall_be_rendered() {
+        let coverage = crate::core::AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(10, 5),
+            changed_lines: Some(AggregatedCoverageCounters::new(4, 3)),
+            ..Default::default()
+        };
+        let html = CoverageGauges::new(&coverage, true).to_html();
+
+        assert!(html.contains(
+            r#"<span class="percent">75.00%</span></div></div><div>Changed lines 3/4</div>"#
+        ));
+    }
 }
@@ -0,0 +1,137 @@
+use htmlescape::{encode_attribute, encode_minimal};
+
+use crate::{core::AggregatedCoverage, html::components::ToHtml};
+
+/// Show the coverage of each item for a single test only, hiding the other
+/// ones. There is no need for a script file, the page is small enough.
+const ON_CHANGE: &str = "document.querySelectorAll('.test-coverage').forEach(e => e.hidden = e.dataset.test !== this.value)";
+
+/// Select the test whose coverage is shown on the page, all of them by default
+pub struct TestFilter<'a> {
+    tests: &'a [String],
+}
+impl<'a> TestFilter<'a> {
+    pub fn new(tests: &'a [String]) -> Self {
+        Self { tests }
+    }
+}
+impl<'a> ToHtml for TestFilter<'a> {
+    fn to_html(&self) -> String {
+        if self.tests.is_empty() {
+            return String::new();
+        }
+
+        let options: String = self
+            .tests
+            .iter()
+            .map(|test| {
+                format!(
+                    r#"<option value="{}">{}</option>"#,
+                    encode_attribute(test),
+                    encode_minimal(test)
+                )
+            })
+            .collect();
+        format!(
+            r#"<div class="test-filter"><label for="test-filter">Coverage of</label><select id="test-filter" onchange="{}"><option value="">All tests</option>{}</select></div>"#,
+            ON_CHANGE, options
+        )
+    }
+}
+
+/// Coverage of an item for all tests, followed by its coverage for each of the
+/// tests of the page, hidden until selected in the `TestFilter`
+pub struct TestsCoverage<'a, TRender: Fn(&AggregatedCoverage) -> String> {
+    coverage: &'a AggregatedCoverage,
+    tests: &'a [String],
+    render: TRender,
+}
+impl<'a, TRender: Fn(&AggregatedCoverage) -> String> TestsCoverage<'a, TRender> {
+    pub fn new(coverage: &'a AggregatedCoverage, tests: &'a [String], render: TRender) -> Self {
+        Self {
+            coverage,
+            tests,
+            render,
+        }
+    }
+}
+impl<'a, TRender: Fn(&AggregatedCoverage) -> String> ToHtml for TestsCoverage<'a, TRender> {
+    fn to_html(&self) -> String {
+        if self.tests.is_empty() {
+            return (self.render)(self.coverage);
+        }
+
+        let all_tests = format!(
+            r#"<div class="test-coverage" data-test="">{}</div>"#,
+            (self.render)(self.coverage)
+        );
+        let each_test = self.tests.iter().map(|test| {
+            format!(
+                r#"<div class="test-coverage" data-test="{}" hidden>{}</div>"#,
+                encode_attribute(test),
+                (self.render)(&self.coverage.for_test(test))
+            )
+        });
+        std::iter::once(all_tests).chain(each_test).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::AggregatedCoverageCounters;
+
+    fn render_lines(coverage: &AggregatedCoverage) -> String {
+        format!("{}/{}", coverage.lines.covered_count, coverage.lines.count)
+    }
+
+    #[test]
+    fn when_there_is_no_test_the_filter_shall_be_empty() {
+        assert_eq!(TestFilter::new(&[]).to_html(), "");
+    }
+
+    #[test]
+    fn when_there_are_tests_the_filter_shall_list_them() {
+        let html = TestFilter::new(&["unit".to_string(), "e2e".to_string()]).to_html();
+        assert!(html.contains(
+            r#"<option value="">All tests</option><option value="unit">unit</option><option value="e2e">e2e</option>"#
+        ));
+    }
+
+    #[test]
+    fn when_there_is_no_test_coverage_shall_be_rendered_as_is() {
+        let coverage = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 3),
+            ..Default::default()
+        };
+        assert_eq!(
+            TestsCoverage::new(&coverage, &[], render_lines).to_html(),
+            "3/4"
+        );
+    }
+
+    #[test]
+    fn when_there_are_tests_coverage_of_each_shall_be_hidden() {
+        let mut coverage = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 3),
+            ..Default::default()
+        };
+        coverage.tests.insert(
+            "unit".to_string(),
+            AggregatedCoverage {
+                lines: AggregatedCoverageCounters::new(4, 2),
+                ..Default::default()
+            },
+        );
+        let tests = vec!["unit".to_string(), "e2e".to_string()];
+
+        assert_eq!(
+            TestsCoverage::new(&coverage, &tests, render_lines).to_html(),
+            concat!(
+                r#"<div class="test-coverage" data-test="">3/4</div>"#,
+                r#"<div class="test-coverage" data-test="unit" hidden>2/4</div>"#,
+                r#"<div class="test-coverage" data-test="e2e" hidden>0/4</div>"#,
+            )
+        );
+    }
+}
@@ -1,19 +1,28 @@
 use std::include_str;
 
 use crate::{
+    aggregation::thresholds::ThresholdRules,
     core::{
-        AggregatedCoverage, AggregatedCoverageCounters, FileLinesProvider, LinksComputer, Renderer,
-        TestedContainer, TestedFile, WithPath,
+        AggregatedCoverage, AggregatedCoverageCounters, FileLinesProvider, LinksComputer,
+        RenderedFile, Renderer, TestedContainer, TestedFile, WithPath,
     },
     html::{
-        colors::{get_percentage_class, render_optional_percentage},
+        colors::{
+            get_percentage_class, get_percentage_delta_class, render_optional_percentage,
+            render_optional_percentage_delta,
+        },
         components::{Div, Img, Link, Text, ToHtml},
     },
 };
 
 use super::components::{
-    chip::render_aggregated_coverage_chips, code_line::CodeLines, file_icon::FileIcon,
-    function::FunctionDefs, gauges::CoverageGauges, navigation::Navigation,
+    chip::render_aggregated_coverage_chips,
+    code_line::CodeLines,
+    file_icon::FileIcon,
+    function::FunctionDefs,
+    gauges::CoverageGauges,
+    navigation::Navigation,
+    test_filter::{TestFilter, TestsCoverage},
 };
 
 const DEFAULT_CSS: &str = include_str!("resources/html_light_renderer.css");
@@ -26,11 +35,68 @@ const FUNCTION_UNCOVERED_SVG: &str = include_str!("resources/function_uncovered.
 
 pub struct HtmlLightRenderer<TLinksComputer: LinksComputer> {
     links_computer: TLinksComputer,
+    threshold_rules: ThresholdRules,
+}
+
+/// Columns of the coverage of the rows of a page
+#[derive(Clone, Copy)]
+struct CoverageColumns {
+    with_regions: bool,
+    with_deltas: bool,
+}
+impl CoverageColumns {
+    fn of(coverage: &AggregatedCoverage) -> Self {
+        CoverageColumns {
+            with_regions: coverage.has_regions(),
+            with_deltas: coverage.baseline.is_some(),
+        }
+    }
 }
 
 impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
     pub fn new(links_computer: TLinksComputer) -> Self {
-        HtmlLightRenderer { links_computer }
+        HtmlLightRenderer {
+            links_computer,
+            threshold_rules: ThresholdRules::default(),
+        }
+    }
+
+    pub fn with_threshold_rules(self, threshold_rules: ThresholdRules) -> Self {
+        HtmlLightRenderer {
+            threshold_rules,
+            ..self
+        }
+    }
+
+    /// Highlight the row of a module or a file below the thresholds of its
+    /// rule, the violated thresholds being listed in its tooltip
+    fn highlight_threshold_violations<'a>(
+        &self,
+        row: Div<'a>,
+        item: &impl WithPath,
+        coverage: &AggregatedCoverage,
+    ) -> Div<'a> {
+        let Some(rule_violations) =
+            self.threshold_rules
+                .check(&item.get_path_string(), item.is_dir(), coverage)
+        else {
+            return row;
+        };
+        let violations = rule_violations
+            .violations
+            .iter()
+            .map(|violation| {
+                format!(
+                    "{} {:.2}% < {:.2}%",
+                    violation.kind, violation.percentage, violation.threshold
+                )
+            })
+            .collect::<Vec<_>>();
+        row.with_class("below-threshold").with_title(&format!(
+            "Below the thresholds of '{}': {}",
+            rule_violations.pattern,
+            violations.join(", ")
+        ))
     }
 
     fn render_aggregated_counters(counters: &AggregatedCoverageCounters) -> Vec<Div> {
@@ -49,44 +115,79 @@ impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
         ]
     }
 
-    fn render_aggregated_coverage(coverage: &AggregatedCoverage) -> impl Iterator<Item = Div> {
-        vec![
-            Self::render_aggregated_counters(&coverage.lines),
-            Self::render_aggregated_counters(&coverage.functions),
-            Self::render_aggregated_counters(&coverage.branches),
-        ]
-        .into_iter()
-        .flatten()
+    fn render_aggregated_coverage(
+        coverage: &AggregatedCoverage,
+        columns: CoverageColumns,
+    ) -> impl Iterator<Item = Div<'_>> {
+        let deltas = coverage.get_percentage_deltas();
+        let mut counters = vec![];
+        for (counters_of_kind, delta) in [&coverage.lines, &coverage.functions, &coverage.branches]
+            .into_iter()
+            .zip(deltas)
+        {
+            counters.push(Self::render_aggregated_counters(counters_of_kind));
+            if columns.with_deltas {
+                counters.push(vec![Div::new()
+                    .with_class("coverage-delta")
+                    .with_class(get_percentage_delta_class(delta))
+                    .with_text(&render_optional_percentage_delta(delta))]);
+            }
+        }
+        if columns.with_regions {
+            counters.push(Self::render_aggregated_counters(&coverage.regions));
+        }
+        counters.into_iter().flatten()
+    }
+
+    /// Coverage cells of a row, for all tests and for each test of the page
+    fn render_row_coverage<'a>(
+        coverage: &'a AggregatedCoverage,
+        tests: &'a [String],
+        columns: CoverageColumns,
+    ) -> impl ToHtml + 'a {
+        TestsCoverage::new(coverage, tests, move |coverage| {
+            Self::render_aggregated_coverage(coverage, columns)
+                .map(|cell| cell.to_html())
+                .collect()
+        })
     }
 
     fn render_file_row<'a>(
         &'a self,
         current_page: &impl WithPath,
         file: &'a impl TestedFile,
-    ) -> Div {
+        tests: &'a [String],
+        columns: CoverageColumns,
+    ) -> Div<'a> {
         let link = self.links_computer.get_link_to(current_page, file);
         let img_src = self.links_computer.get_link_to_resource(
             current_page,
             FileIcon::get_icon_key(file).unwrap_or_default(),
         );
 
-        Div::new().with_child(
-            Div::new()
-                .with_class("file-row")
-                .with_child(
-                    Div::new()
-                        .with_class("file-logo")
-                        .with_child(Img::new(&img_src, "File logo")),
-                )
-                .with_child(
-                    Div::new()
-                        .with_class("item-name")
-                        .with_child(Link::from_link_payload(link)),
-                )
-                .with_children(Self::render_aggregated_coverage(
-                    file.get_aggregated_coverage(),
-                )),
-        )
+        let row = Div::new()
+            .with_class("file-row")
+            .with_child(
+                Div::new()
+                    .with_class("file-logo")
+                    .with_child(Img::new(&img_src, "File logo")),
+            )
+            .with_child(
+                Div::new()
+                    .with_class("item-name")
+                    .with_child(Link::from_link_payload(link)),
+            )
+            .with_child(Self::render_row_coverage(
+                file.get_aggregated_coverage(),
+                tests,
+                columns,
+            ));
+
+        Div::new().with_child(self.highlight_threshold_violations(
+            row,
+            file,
+            file.get_aggregated_coverage(),
+        ))
     }
 
     fn render_module_row<'a>(
@@ -94,29 +195,39 @@ impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
         root: &impl WithPath,
         current_page: &impl WithPath,
         module: &'a impl TestedContainer,
+        tests: &'a [String],
+        columns: CoverageColumns,
     ) -> Div<'a> {
         let submodules = module
             .get_container_children()
-            .map(|module| self.render_module_row(root, current_page, module));
+            .map(|module| self.render_module_row(root, current_page, module, tests, columns));
 
         let files = module
             .get_code_file_children()
-            .map(|file| self.render_file_row(current_page, file));
+            .map(|file| self.render_file_row(current_page, file, tests, columns));
+
+        let row = Div::new()
+            .with_class("module-row")
+            .with_child(
+                Div::new()
+                    .with_class("item-name")
+                    .with_child(Link::from_link_payload(
+                        self.links_computer.get_link_to(current_page, module),
+                    )),
+            )
+            .with_child(Self::render_row_coverage(
+                module.get_aggregated_coverage(),
+                tests,
+                columns,
+            ));
 
         Div::new().with_class("module-div").with_child(
             Div::new()
-                .with_child(
-                    Div::new()
-                        .with_class("module-row")
-                        .with_child(Div::new().with_class("item-name").with_child(
-                            Link::from_link_payload(
-                                self.links_computer.get_link_to(current_page, module),
-                            ),
-                        ))
-                        .with_children(Self::render_aggregated_coverage(
-                            module.get_aggregated_coverage(),
-                        )),
-                )
+                .with_child(self.highlight_threshold_violations(
+                    row,
+                    module,
+                    module.get_aggregated_coverage(),
+                ))
                 .with_child(
                     Div::new()
                         .with_class("module-children")
@@ -131,6 +242,8 @@ impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
         root: &impl WithPath,
         current_page: &impl WithPath,
         module: &'a impl TestedContainer,
+        tests: &'a [String],
+        columns: CoverageColumns,
     ) -> Div<'a> {
         let module_img_href = self
             .links_computer
@@ -150,22 +263,32 @@ impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
                     )),
             )
             .with_child(Div::new().with_class("fill"))
-            .with_children(render_aggregated_coverage_chips(
+            .with_child(TestsCoverage::new(
                 module.get_aggregated_coverage(),
+                tests,
+                |coverage| {
+                    render_aggregated_coverage_chips(coverage)
+                        .map(|chip| chip.to_html())
+                        .collect()
+                },
             ))
             .with_child(Div::new().with_class("w-20"));
 
         let submodules = module
             .get_container_children()
-            .map(|module| self.render_module_row(root, current_page, module));
+            .map(|module| self.render_module_row(root, current_page, module, tests, columns));
 
         let files = module
             .get_code_file_children()
-            .map(|file| self.render_file_row(current_page, file));
+            .map(|file| self.render_file_row(current_page, file, tests, columns));
 
         Div::new()
             .with_class("top-module-card")
-            .with_child(top_module_div)
+            .with_child(self.highlight_threshold_violations(
+                top_module_div,
+                module,
+                module.get_aggregated_coverage(),
+            ))
             .with_child(
                 Div::new()
                     .with_class("module-children")
@@ -174,6 +297,41 @@ impl<TLinksComputer: LinksComputer> HtmlLightRenderer<TLinksComputer> {
             )
     }
 
+    /// Files of the module and of its submodules whose coverage dropped since
+    /// the baseline, named by their path in the module
+    fn render_files_losing_coverage<'a>(
+        &'a self,
+        current_page: &impl WithPath,
+        module: &'a impl TestedContainer,
+        columns: CoverageColumns,
+        rows: &mut Vec<Div<'a>>,
+    ) {
+        let files = module
+            .get_code_file_children()
+            .filter(|file| file.get_aggregated_coverage().has_lost_coverage());
+        for file in files {
+            let link = self.links_computer.get_link_to(current_page, file);
+            let path = file.get_path_relative_to(&current_page.get_path());
+            rows.push(
+                Div::new()
+                    .with_class("file-row")
+                    .with_child(
+                        Div::new()
+                            .with_class("item-name")
+                            .with_child(Link::from_text(&link.link, &path.to_string_lossy())),
+                    )
+                    .with_child(Self::render_row_coverage(
+                        file.get_aggregated_coverage(),
+                        &[],
+                        columns,
+                    )),
+            );
+        }
+        for submodule in module.get_container_children() {
+            self.render_files_losing_coverage(current_page, submodule, columns, rows);
+        }
+    }
+
     fn render_layout(&self, current: &impl WithPath, content: String) -> String {
         return format!(
             "<html>
@@ -236,9 +394,17 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
         root: &impl WithPath,
         module: &impl TestedContainer,
     ) -> String {
+        // Rows of a page shall all have the same columns
+        let columns = CoverageColumns::of(module.get_aggregated_coverage());
+        let tests = module
+            .get_aggregated_coverage()
+            .tests
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
         let top_level_code_files = module
             .get_code_file_children()
-            .map(|file| self.render_file_row(module, file));
+            .map(|file| self.render_file_row(module, file, &tests, columns));
 
         let mut main = Div::new().with_child(
             Div::new()
@@ -246,7 +412,12 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
                 .with_class("header")
                 .with_child(self.render_title_with_img(module, "module-main.svg"))
                 .with_child(Navigation::new(&self.links_computer, root, module))
-                .with_child(CoverageGauges::new(module.get_aggregated_coverage(), true)),
+                .with_child(TestFilter::new(&tests))
+                .with_child(TestsCoverage::new(
+                    module.get_aggregated_coverage(),
+                    &tests,
+                    |coverage| CoverageGauges::new(coverage, true).to_html(),
+                )),
         );
         if module.get_code_file_children().count() > 0 {
             main = main.with_child(
@@ -257,11 +428,26 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
                 ),
             )
         }
-        main = main.with_children(
-            module
-                .get_container_children()
-                .map(|submodule| self.render_top_module_row(root, module, submodule)),
-        );
+        main =
+            main.with_children(module.get_container_children().map(|submodule| {
+                self.render_top_module_row(root, module, submodule, &tests, columns)
+            }));
+
+        let mut files_losing_coverage = vec![];
+        self.render_files_losing_coverage(module, module, columns, &mut files_losing_coverage);
+        if !files_losing_coverage.is_empty() {
+            main = main.with_child(
+                Div::new()
+                    .with_class("details-card")
+                    .with_id("lost-coverage")
+                    .with_child(Text::h2("Files that lost coverage"))
+                    .with_child(
+                        Div::new()
+                            .with_class("module-children")
+                            .with_children(files_losing_coverage.into_iter()),
+                    ),
+            );
+        }
 
         return self.render_layout(module, main.to_html());
     }
@@ -271,8 +457,25 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
         root: &impl WithPath,
         file: &impl TestedFile,
         file_provider: &impl FileLinesProvider,
-    ) -> String {
-        let lines = file_provider.get_file_lines().unwrap();
+    ) -> RenderedFile {
+        // A missing source shall not prevent the rest of the report from being
+        // rendered, the lines of the report are listed instead
+        let (missing_source_warning, code_lines) = match file_provider.get_file_lines() {
+            Ok(lines) => (None, CodeLines::new(file, lines)),
+            Err(e) => (
+                Some(Div::new().with_class("missing-source").with_text(&format!(
+                    "The source of this file could not be read ({}), only the lines of the coverage report are listed.",
+                    e
+                ))),
+                CodeLines::without_source(file),
+            ),
+        };
+        let is_source_missing = missing_source_warning.is_some();
+        let source_root = file.get_source_root().map(|root| {
+            Div::new()
+                .with_class("source-root")
+                .with_text(&format!("Source found in {}", root.display()))
+        });
 
         let main = Div::new()
             .with_child(
@@ -283,6 +486,8 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
                         FileIcon::get_icon_key(file).unwrap_or_default(),
                     ))
                     .with_child(Navigation::new(&self.links_computer, root, file))
+                    .with_children(source_root.into_iter())
+                    .with_children(missing_source_warning.into_iter())
                     .with_child(CoverageGauges::new(file.get_aggregated_coverage(), true)),
             )
             .with_child(
@@ -290,7 +495,7 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
                     .with_class("details-card")
                     .with_id("lines")
                     .with_child(Text::h2("Lines"))
-                    .with_child(CodeLines::new(file, lines)),
+                    .with_child(code_lines),
             )
             .with_child(
                 Div::new()
@@ -300,7 +505,10 @@ impl<TLinksComputer: LinksComputer> Renderer for HtmlLightRenderer<TLinksCompute
                     .with_child(FunctionDefs::new(file, &self.links_computer)),
             );
 
-        self.render_layout(file, main.to_html())
+        RenderedFile {
+            content: self.render_layout(file, main.to_html()),
+            is_source_missing,
+        }
     }
 
     fn get_required_resources(
@@ -1,4 +1,6 @@
-use crate::core::{FileLinesProvider, Renderer, TestedContainer, TestedFile, WithPath};
+use crate::core::{
+    FileLinesProvider, RenderedFile, Renderer, TestedContainer, TestedFile, WithPath,
+};
 
 pub struct MockRenderer;
 impl Renderer for MockRenderer {
@@ -14,9 +16,12 @@ impl Renderer for MockRenderer {
         &self,
         _root: &impl WithPath,
         file: &impl TestedFile,
-        _file_provider: &impl FileLinesProvider,
-    ) -> String {
-        format!("Report for file {}", file.get_name())
+        file_provider: &impl FileLinesProvider,
+    ) -> RenderedFile {
+        RenderedFile {
+            content: format!("Report for file {}", file.get_name()),
+            is_source_missing: file_provider.get_file_lines().is_err(),
+        }
     }
 
     fn get_required_resources(
@@ -1,9 +1,9 @@
 use crate::{
     core::{
-        AggregatedCoverage, AggregatedCoverageCounters, FileLinesProvider, Renderer,
+        AggregatedCoverage, AggregatedCoverageCounters, FileLinesProvider, RenderedFile, Renderer,
         TestedContainer, TestedFile, WithPath,
     },
-    html::colors::render_optional_percentage,
+    html::colors::{render_optional_percentage, render_optional_percentage_delta},
 };
 
 pub struct TextSinglePageRenderer;
@@ -18,26 +18,66 @@ impl TextSinglePageRenderer {
         )
     }
 
-    fn render_aggregated_coverage(coverage: &AggregatedCoverage) -> String {
-        format!(
+    /// Counters followed by the evolution since the baseline, when the report
+    /// is compared with one
+    fn render_counters_with_delta(
+        counters: &AggregatedCoverageCounters,
+        delta: Option<f32>,
+        with_deltas: bool,
+    ) -> String {
+        match with_deltas {
+            true => format!(
+                "{} {: >9}",
+                Self::render_aggregated_counters(counters),
+                render_optional_percentage_delta(delta)
+            ),
+            false => Self::render_aggregated_counters(counters),
+        }
+    }
+
+    fn render_aggregated_coverage(
+        coverage: &AggregatedCoverage,
+        with_regions: bool,
+        with_deltas: bool,
+    ) -> String {
+        let [lines_delta, functions_delta, branches_delta] = coverage.get_percentage_deltas();
+        let mut output = format!(
             "Lines {}    Functions {}    Branches {}",
-            Self::render_aggregated_counters(&coverage.lines),
-            Self::render_aggregated_counters(&coverage.functions),
-            Self::render_aggregated_counters(&coverage.branches),
-        )
+            Self::render_counters_with_delta(&coverage.lines, lines_delta, with_deltas),
+            Self::render_counters_with_delta(&coverage.functions, functions_delta, with_deltas),
+            Self::render_counters_with_delta(&coverage.branches, branches_delta, with_deltas),
+        );
+        if with_regions {
+            output.push_str(&format!(
+                "    Regions {}",
+                Self::render_aggregated_counters(&coverage.regions)
+            ));
+        }
+        output
     }
 
-    fn render_line(level: u32, name: &str, coverage: &AggregatedCoverage) -> String {
+    fn render_line(
+        level: u32,
+        name: &str,
+        coverage: &AggregatedCoverage,
+        with_regions: bool,
+        with_deltas: bool,
+    ) -> String {
         let name_wit_padding = "  ".repeat(level as usize) + name;
         format!(
             "{: <50} {}
 ",
             name_wit_padding,
-            Self::render_aggregated_coverage(coverage)
+            Self::render_aggregated_coverage(coverage, with_regions, with_deltas).trim_end()
         )
     }
 
-    fn render_module(module: &impl TestedContainer, level: u32) -> String {
+    fn render_module(
+        module: &impl TestedContainer,
+        level: u32,
+        with_regions: bool,
+        with_deltas: bool,
+    ) -> String {
         let mut output = String::new();
 
         for file in module.get_code_file_children() {
@@ -45,6 +85,8 @@ impl TextSinglePageRenderer {
                 level,
                 file.get_name(),
                 file.get_aggregated_coverage(),
+                with_regions,
+                with_deltas,
             ));
         }
         for submodule in module.get_container_children() {
@@ -52,25 +94,126 @@ impl TextSinglePageRenderer {
                 level,
                 submodule.get_name(),
                 submodule.get_aggregated_coverage(),
+                with_regions,
+                with_deltas,
+            ));
+            output.push_str(&Self::render_module(
+                submodule,
+                level + 1,
+                with_regions,
+                with_deltas,
+            ));
+        }
+        output
+    }
+
+    /// Changed lines of the files changed by the diff, with the uncovered ones
+    fn render_changed_files(module: &impl TestedContainer) -> String {
+        let mut output = String::new();
+
+        for file in module.get_code_file_children() {
+            let changed_lines = match &file.get_aggregated_coverage().changed_lines {
+                Some(changed_lines) if changed_lines.count > 0 => changed_lines,
+                _ => continue,
+            };
+            let uncovered_lines = file
+                .get_tested_lines()
+                .into_iter()
+                .filter(|line| {
+                    file.is_line_changed(*line) && file.get_line_coverage(*line) == Some(0)
+                })
+                .map(|line| line.to_string())
+                .collect::<Vec<_>>();
+            let uncovered_lines = match uncovered_lines.is_empty() {
+                true => String::new(),
+                false => format!("    Uncovered: {}", uncovered_lines.join(", ")),
+            };
+            output.push_str(&format!(
+                "  {: <48} {}{}\n",
+                file.get_path_string(),
+                Self::render_aggregated_counters(changed_lines),
+                uncovered_lines
             ));
-            output.push_str(&Self::render_module(submodule, level + 1));
+        }
+        for submodule in module.get_container_children() {
+            output.push_str(&Self::render_changed_files(submodule));
         }
         output
     }
 
+    /// Summary restricted to the lines changed by a diff, when its coverage
+    /// is reported
+    fn render_changed_lines(root: &impl TestedContainer) -> String {
+        match &root.get_aggregated_coverage().changed_lines {
+            Some(changed_lines) => format!(
+                "\nChanged lines:\n  - Lines     {}\n\n{}",
+                Self::render_aggregated_counters(changed_lines),
+                Self::render_changed_files(root)
+            ),
+            None => String::new(),
+        }
+    }
+
+    /// Files whose coverage dropped since the baseline, with the evolution of
+    /// their lines, functions and branches
+    fn render_files_losing_coverage(module: &impl TestedContainer) -> String {
+        let mut output = String::new();
+
+        for file in module.get_code_file_children() {
+            let coverage = file.get_aggregated_coverage();
+            if !coverage.has_lost_coverage() {
+                continue;
+            }
+            let [lines_delta, functions_delta, branches_delta] = coverage.get_percentage_deltas();
+            let line = format!(
+                "  {: <48} Lines {: >9}    Functions {: >9}    Branches {: >9}",
+                file.get_path_string(),
+                render_optional_percentage_delta(lines_delta),
+                render_optional_percentage_delta(functions_delta),
+                render_optional_percentage_delta(branches_delta),
+            );
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        for submodule in module.get_container_children() {
+            output.push_str(&Self::render_files_losing_coverage(submodule));
+        }
+        output
+    }
+
+    fn render_lost_coverage(root: &impl TestedContainer) -> String {
+        match Self::render_files_losing_coverage(root) {
+            files if files.is_empty() => String::new(),
+            files => format!("\nFiles that lost coverage:\n{}", files),
+        }
+    }
+
     fn render_root(root: &impl TestedContainer) -> String {
+        let coverage = root.get_aggregated_coverage();
+        let regions = match coverage.has_regions() {
+            true => format!(
+                "  - Regions   {}\n",
+                Self::render_aggregated_counters(&coverage.regions)
+            ),
+            false => String::new(),
+        };
+        let with_deltas = coverage.baseline.is_some();
+        let [lines_delta, functions_delta, branches_delta] = coverage.get_percentage_deltas();
         format!(
             r#"{}:
   - Lines     {}
   - Functions {}
   - Branches  {}
-
+{}
 Details:
 "#,
             root.get_name(),
-            Self::render_aggregated_counters(&root.get_aggregated_coverage().lines),
-            Self::render_aggregated_counters(&root.get_aggregated_coverage().functions),
-            Self::render_aggregated_counters(&root.get_aggregated_coverage().branches)
+            Self::render_counters_with_delta(&coverage.lines, lines_delta, with_deltas).trim_end(),
+            Self::render_counters_with_delta(&coverage.functions, functions_delta, with_deltas)
+                .trim_end(),
+            Self::render_counters_with_delta(&coverage.branches, branches_delta, with_deltas)
+                .trim_end(),
+            regions
         )
     }
 }
@@ -88,7 +231,7 @@ impl Renderer for TextSinglePageRenderer {
         _root: &impl WithPath,
         _file: &impl TestedFile,
         _file_provider: &impl FileLinesProvider,
-    ) -> String {
+    ) -> RenderedFile {
         unimplemented!("Text renderer only display a summary of the coverage")
     }
 
@@ -97,11 +240,19 @@ impl Renderer for TextSinglePageRenderer {
         _root: &impl WithPath,
         module: &impl TestedContainer,
     ) -> String {
+        let coverage = module.get_aggregated_coverage();
         format!(
             "{}
-{}",
+{}{}{}",
             Self::render_root(module),
-            Self::render_module(module, 1)
+            Self::render_module(
+                module,
+                1,
+                coverage.has_regions(),
+                coverage.baseline.is_some()
+            ),
+            Self::render_changed_lines(module),
+            Self::render_lost_coverage(module)
         )
     }
 }
@@ -128,6 +279,79 @@ Details:
   main.cpp                                         Lines        3/4   75.00%    Functions        2/2  100.00%    Branches        1/2   50.00%
   module                                           Lines        2/2  100.00%    Functions        1/1  100.00%    Branches        0/0        -
     nested.cpp                                     Lines        2/2  100.00%    Functions        1/1  100.00%    Branches        0/0        -
+"#
+        );
+    }
+
+    #[test]
+    fn when_rendering_report_with_regions_it_shall_display_regions_column() {
+        let report = AggregatedFixtures::get_simple_report_with_regions();
+        let renderer = TextSinglePageRenderer;
+        let rendered = renderer.render_module_coverage_details(&report, &report);
+        assert_eq!(
+            rendered,
+            r#"Test report:
+  - Lines            1/2   50.00%
+  - Functions        0/0        -
+  - Branches         0/0        -
+  - Regions          3/4   75.00%
+
+Details:
+
+  lib.rs                                           Lines        1/2   50.00%    Functions        0/0        -    Branches        0/0        -    Regions        3/4   75.00%
+"#
+        );
+    }
+
+    #[test]
+    fn when_rendering_the_coverage_of_a_diff_it_shall_summarize_the_changed_lines() {
+        let report = AggregatedFixtures::get_simple_report_with_changed_lines();
+        let renderer = TextSinglePageRenderer;
+        let rendered = renderer.render_module_coverage_details(&report, &report);
+        assert_eq!(
+            rendered,
+            r#"Test report:
+  - Lines            3/6   50.00%
+  - Functions        0/0        -
+  - Branches         0/0        -
+
+Details:
+
+  main.cpp                                         Lines        2/3   66.67%    Functions        0/0        -    Branches        0/0        -
+  module                                           Lines        1/3   33.33%    Functions        0/0        -    Branches        0/0        -
+    nested.cpp                                     Lines        0/2    0.00%    Functions        0/0        -    Branches        0/0        -
+    other.cpp                                      Lines        1/1  100.00%    Functions        0/0        -    Branches        0/0        -
+
+Changed lines:
+  - Lines            1/4   25.00%
+
+  main.cpp                                                1/2   50.00%    Uncovered: 2
+  module/nested.cpp                                       0/2    0.00%    Uncovered: 1, 2
+"#
+        );
+    }
+
+    #[test]
+    fn when_comparing_with_a_baseline_it_shall_display_deltas_and_files_that_lost_coverage() {
+        let report = AggregatedFixtures::get_simple_report_with_baseline();
+        let renderer = TextSinglePageRenderer;
+        let rendered = renderer.render_module_coverage_details(&report, &report);
+        assert_eq!(
+            rendered,
+            r#"Test report:
+  - Lines            4/6   66.67%  ▼ 19.05%
+  - Functions        0/0        -
+  - Branches         0/0        -
+
+Details:
+
+  main.cpp                                         Lines        2/3   66.67%  ▼ 33.33%    Functions        0/0        -              Branches        0/0        -
+  module                                           Lines        2/3   66.67%   ▼ 8.33%    Functions        0/0        -              Branches        0/0        -
+    nested.cpp                                     Lines        2/2  100.00%  ▲ 50.00%    Functions        0/0        -              Branches        0/0        -
+    new.cpp                                        Lines        0/1    0.00%              Functions        0/0        -              Branches        0/0        -
+
+Files that lost coverage:
+  main.cpp                                         Lines  ▼ 33.33%    Functions              Branches
 "#
         );
     }
@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use crate::core::{AggregatedCoverage, AggregatedCoverageCounters};
 
 impl AggregatedCoverageCounters {
@@ -8,10 +10,131 @@ impl AggregatedCoverageCounters {
 }
 
 impl AggregatedCoverage {
+    /// A test not known by one of both covers none of its items, which still
+    /// count in the total of the test. Baselines are not summed, the one of a
+    /// module is not the sum of the ones of its files.
     pub fn add(&mut self, other: &Self) {
+        let test_names = self
+            .tests
+            .keys()
+            .chain(other.tests.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        for test_name in test_names {
+            let mut test_coverage = self
+                .tests
+                .remove(&test_name)
+                .unwrap_or_else(|| self.without_tests_coverage());
+            match other.tests.get(&test_name) {
+                Some(other_test_coverage) => test_coverage.add(other_test_coverage),
+                None => test_coverage.add(&other.without_tests_coverage()),
+            }
+            self.tests.insert(test_name, test_coverage);
+        }
+
         self.lines.add(&other.lines);
         self.functions.add(&other.functions);
         self.branches.add(&other.branches);
+        self.regions.add(&other.regions);
+        self.changed_lines = match (self.changed_lines.take(), &other.changed_lines) {
+            (Some(mut changed_lines), Some(other_changed_lines)) => {
+                changed_lines.add(other_changed_lines);
+                Some(changed_lines)
+            }
+            (changed_lines, other_changed_lines) => {
+                changed_lines.or_else(|| other_changed_lines.clone())
+            }
+        };
+    }
+
+    /// Evolution of the percentages of lines, functions and branches since
+    /// the baseline, when it is known
+    pub fn get_percentage_deltas(&self) -> [Option<f32>; 3] {
+        match &self.baseline {
+            Some(baseline) => [
+                self.lines.percentage_delta(&baseline.lines),
+                self.functions.percentage_delta(&baseline.functions),
+                self.branches.percentage_delta(&baseline.branches),
+            ],
+            None => [None; 3],
+        }
+    }
+
+    pub fn has_lost_coverage(&self) -> bool {
+        self.get_percentage_deltas()
+            .iter()
+            .any(|delta| delta.is_some_and(|delta| delta < 0.))
+    }
+
+    /// Same totals, with nothing covered. Regions are not known per test.
+    fn without_tests_coverage(&self) -> Self {
+        Self {
+            lines: AggregatedCoverageCounters::new(self.lines.count, 0),
+            functions: AggregatedCoverageCounters::new(self.functions.count, 0),
+            branches: AggregatedCoverageCounters::new(self.branches.count, 0),
+            ..Default::default()
+        }
+    }
+
+    /// Coverage contributed by a single test
+    pub fn for_test(&self, test_name: &str) -> Self {
+        self.tests
+            .get(test_name)
+            .cloned()
+            .unwrap_or_else(|| self.without_tests_coverage())
+    }
+
+    /// Items of the section (all tests merged) covered by the section of a
+    /// single test
+    pub fn from_test_section(
+        value: &lcov::report::section::Value,
+        test_value: &lcov::report::section::Value,
+    ) -> Self {
+        let covered_lines_count = value
+            .lines
+            .keys()
+            .filter(|key| test_value.lines.get(key).is_some_and(|line| line.count > 0))
+            .count() as u32;
+        let covered_functions_count = value
+            .functions
+            .keys()
+            .filter(|key| {
+                test_value
+                    .functions
+                    .get(key)
+                    .is_some_and(|function| function.count > 0)
+            })
+            .count() as u32;
+        let covered_branches_count = value
+            .branches
+            .keys()
+            .filter(|key| {
+                test_value
+                    .branches
+                    .get(key)
+                    .and_then(|branch| branch.taken)
+                    .is_some_and(|taken| taken > 0)
+            })
+            .count() as u32;
+
+        Self {
+            lines: AggregatedCoverageCounters::new(value.lines.len() as u32, covered_lines_count),
+            functions: AggregatedCoverageCounters::new(
+                value.functions.len() as u32,
+                covered_functions_count,
+            ),
+            branches: AggregatedCoverageCounters::new(
+                value.branches.len() as u32,
+                covered_branches_count,
+            ),
+            ..Default::default()
+        }
+    }
+
+    /// Regions are only known for some input formats (llvm-cov), they are not
+    /// displayed when none of the aggregated files had any
+    pub fn has_regions(&self) -> bool {
+        self.regions.count > 0
     }
 
     pub fn from_section(value: &lcov::report::section::Value) -> Self {
@@ -40,6 +163,7 @@ impl AggregatedCoverage {
             lines: AggregatedCoverageCounters::new(lines_count, covered_lines_count),
             functions: AggregatedCoverageCounters::new(functions_count, covered_functions_count),
             branches: AggregatedCoverageCounters::new(branches_count, covered_branches_count),
+            ..Default::default()
         }
     }
 }
@@ -60,7 +184,7 @@ mod test {
 
     use crate::{
         aggregation::fixtures::AggregatedFixtures,
-        core::AggregatedCoverage,
+        core::{AggregatedCoverage, AggregatedCoverageCounters},
         test_utils::builders::{InsertBranch, InsertFunction, InsertLine},
     };
 
@@ -99,6 +223,61 @@ mod test {
         assert_aggregated_counters_eq(&other.branches, 0, 0);
     }
 
+    #[test]
+    fn when_adding_an_aggregate_with_regions_the_regions_shall_be_summed() {
+        let mut aggregated = AggregatedFixtures::get_file_aggregates_10_5();
+        let other = AggregatedFixtures::get_file_aggregates_with_regions_8_6();
+        assert!(!aggregated.has_regions());
+
+        aggregated.add(&other);
+        assert!(aggregated.has_regions());
+        assert_aggregated_counters_eq(&aggregated.regions, 8, 6);
+        assert_aggregated_counters_eq(&aggregated.lines, 14, 7);
+    }
+
+    #[test]
+    fn when_adding_aggregates_with_baselines_the_baseline_shall_not_be_summed() {
+        let baseline = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 2),
+            ..Default::default()
+        };
+        let mut aggregated = AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 3),
+            baseline: Some(Box::new(baseline.clone())),
+            ..Default::default()
+        };
+        aggregated.add(&AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 1),
+            baseline: Some(Box::new(AggregatedCoverage {
+                lines: AggregatedCoverageCounters::new(4, 4),
+                ..Default::default()
+            })),
+            ..Default::default()
+        });
+
+        assert_eq!(aggregated.baseline, Some(Box::new(baseline)));
+        assert_eq!(aggregated.get_percentage_deltas(), [Some(0.), None, None]);
+    }
+
+    #[test]
+    fn when_adding_an_aggregate_with_changed_lines_they_shall_be_summed() {
+        let mut aggregated = AggregatedFixtures::get_file_aggregates_10_5();
+        aggregated.add(&AggregatedCoverage {
+            changed_lines: Some(AggregatedCoverageCounters::new(3, 2)),
+            ..Default::default()
+        });
+        aggregated.add(&AggregatedCoverage {
+            changed_lines: Some(AggregatedCoverageCounters::new(0, 0)),
+            ..Default::default()
+        });
+        aggregated.add(&AggregatedFixtures::get_file_aggregates_10_5());
+
+        assert_eq!(
+            aggregated.changed_lines,
+            Some(AggregatedCoverageCounters::new(3, 2))
+        );
+    }
+
     #[test]
     fn when_creating_from_an_empty_section_line_counts_shall_be_0() {
         let section_value = SectionValue::default();
@@ -189,4 +368,42 @@ mod test {
         let aggregated = AggregatedCoverage::from_section(&section_value);
         assert_aggregated_counters_eq(&aggregated.branches, 3, 2);
     }
+
+    #[test]
+    fn when_adding_aggregates_a_test_unknown_by_one_shall_count_its_items_as_not_covered() {
+        let unit = AggregatedCoverage::from_test_section(
+            &SectionValue::default().insert_line(1, 1).insert_line(2, 0),
+            &SectionValue::default().insert_line(1, 1),
+        );
+        let mut aggregated = AggregatedCoverage::from_section(
+            &SectionValue::default().insert_line(1, 1).insert_line(2, 0),
+        );
+        aggregated.tests.insert("unit".to_string(), unit);
+        let other = AggregatedCoverage::from_section(
+            &SectionValue::default().insert_line(1, 1).insert_line(2, 1),
+        );
+
+        aggregated.add(&other);
+
+        assert_aggregated_counters_eq(&aggregated.lines, 4, 3);
+        assert_aggregated_counters_eq(&aggregated.for_test("unit").lines, 4, 1);
+        assert_aggregated_counters_eq(&aggregated.for_test("e2e").lines, 4, 0);
+    }
+
+    #[test]
+    fn when_creating_from_a_test_section_only_items_it_covers_shall_be_covered() {
+        let section_value = SectionValue::default()
+            .insert_line(1, 2)
+            .insert_line(2, 1)
+            .insert_line(3, 0)
+            .insert_branch(1, 1);
+        let test_section_value = SectionValue::default()
+            .insert_line(1, 1)
+            .insert_line(2, 0)
+            .insert_branch(1, 1);
+
+        let aggregated = AggregatedCoverage::from_test_section(&section_value, &test_section_value);
+        assert_aggregated_counters_eq(&aggregated.lines, 3, 1);
+        assert_aggregated_counters_eq(&aggregated.branches, 1, 1);
+    }
 }
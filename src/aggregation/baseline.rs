@@ -0,0 +1,162 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    core::{AggregatedCoverage, TestedContainer, TestedFile, WithPath},
+    input::aggregator_input::AggregatorInput,
+};
+
+use super::tested_root::{BuildContext, TestedRoot};
+
+/// Coverage of a baseline report, for another report to be compared with it.
+/// Files, modules and roots are found by their path in the report: the prefix
+/// of their root joined with their path in it, so that both reports don't need
+/// to have the same prefix.
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline {
+    root: AggregatedCoverage,
+    directories: BTreeMap<PathBuf, AggregatedCoverage>,
+    files: BTreeMap<PathBuf, AggregatedCoverage>,
+}
+
+impl Baseline {
+    /// The sources the baseline was generated from are not known: its lines
+    /// are excluded by the markers of the sources of the report it is compared
+    /// with, read from the same revision. The lines excluded from the report
+    /// are then excluded from the baseline too, instead of making a difference
+    /// of their own, unless they moved since the baseline.
+    pub fn from_input(input: AggregatorInput, context: &BuildContext) -> Self {
+        let context = BuildContext {
+            source_rev: context.source_rev.clone(),
+            exclusion_markers: context.exclusion_markers.clone(),
+            ..Default::default()
+        };
+        Self::from_root(&TestedRoot::new(input, &context))
+    }
+
+    pub fn from_root(root: &TestedRoot) -> Self {
+        let mut baseline = Baseline {
+            root: Self::get_compared_coverage(root.get_aggregated_coverage()),
+            ..Default::default()
+        };
+        baseline.add_module(root.get_prefix(), root);
+        baseline
+    }
+
+    /// Only the overall coverage is compared
+    fn get_compared_coverage(coverage: &AggregatedCoverage) -> AggregatedCoverage {
+        AggregatedCoverage {
+            tests: BTreeMap::new(),
+            changed_lines: None,
+            baseline: None,
+            ..coverage.clone()
+        }
+    }
+
+    fn add_module(&mut self, directory: &Path, module: &impl TestedContainer) {
+        self.directories.insert(
+            directory.to_path_buf(),
+            Self::get_compared_coverage(module.get_aggregated_coverage()),
+        );
+        for file in module.get_code_file_children() {
+            self.files.insert(
+                directory.join(file.get_name()),
+                Self::get_compared_coverage(file.get_aggregated_coverage()),
+            );
+        }
+        for submodule in module.get_container_children() {
+            self.add_module(&directory.join(submodule.get_name()), submodule);
+        }
+    }
+
+    /// Coverage of the whole baseline report
+    pub fn get_root_coverage(&self) -> &AggregatedCoverage {
+        &self.root
+    }
+
+    /// Coverage of the module or root of the baseline holding the sources of
+    /// this directory
+    pub fn get_directory_coverage(&self, directory: &Path) -> Option<&AggregatedCoverage> {
+        self.directories.get(directory)
+    }
+
+    pub fn get_file_coverage(&self, path: &Path) -> Option<&AggregatedCoverage> {
+        self.files.get(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        aggregation::fixtures::AggregatedFixtures,
+        core::AggregatedCoverageCounters,
+        input::config::ExclusionMarkers,
+        test_utils::builders::{InsertLine, InsertSection},
+    };
+    use lcov::report::{section::Value as SectionValue, Report as LcovReport};
+
+    #[test]
+    fn when_building_a_baseline_all_files_of_the_root_shall_be_found_by_their_path() {
+        let baseline = Baseline::from_root(&AggregatedFixtures::get_simple_full_report());
+
+        assert_eq!(
+            baseline
+                .get_file_coverage(Path::new("module/nested.cpp"))
+                .map(|coverage| &coverage.lines),
+            Some(&AggregatedCoverageCounters::new(2, 2))
+        );
+        assert!(baseline.get_file_coverage(Path::new("main.cpp")).is_some());
+        assert!(baseline
+            .get_file_coverage(Path::new("nested.cpp"))
+            .is_none());
+    }
+
+    #[test]
+    fn when_building_a_baseline_modules_and_root_shall_be_found_by_their_directory() {
+        let root = AggregatedFixtures::get_simple_full_report();
+        let baseline = Baseline::from_root(&root);
+
+        assert_eq!(
+            baseline.get_directory_coverage(Path::new("")),
+            Some(baseline.get_root_coverage())
+        );
+        assert_eq!(
+            baseline
+                .get_directory_coverage(Path::new("module"))
+                .map(|coverage| &coverage.lines),
+            Some(&AggregatedCoverageCounters::new(2, 2))
+        );
+        assert!(baseline
+            .get_directory_coverage(Path::new("main.cpp"))
+            .is_none());
+    }
+
+    #[test]
+    fn when_building_a_baseline_from_an_input_the_lines_excluded_from_the_report_shall_be_excluded()
+    {
+        let report = LcovReport::new().insert_section(
+            "tests/fixtures/my_code.cpp",
+            SectionValue::default()
+                .insert_line(4, 1)
+                .insert_line(5, 0)
+                .insert_line(7, 1),
+        );
+        let context = BuildContext {
+            exclusion_markers: Some(ExclusionMarkers {
+                line: "return 1;".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let baseline = Baseline::from_input(AggregatorInput::new(report), &context);
+
+        assert_eq!(
+            baseline.get_root_coverage().lines,
+            AggregatedCoverageCounters::new(2, 2)
+        );
+    }
+}
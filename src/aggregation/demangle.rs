@@ -0,0 +1,132 @@
+use cpp_demangle::{DemangleOptions, Symbol};
+
+/// Demangled name of a Rust (legacy or v0) or Itanium C++ symbol, without the
+/// hashes of Rust symbols. `None` when the name isn't mangled.
+/// Rust legacy symbols are valid Itanium ones, so they are tried first.
+pub fn demangle(name: &str) -> Option<String> {
+    if let Ok(demangled) = rustc_demangle::try_demangle(name) {
+        return Some(format!("{:#}", demangled));
+    }
+    Symbol::new(name)
+        .ok()?
+        .demangle_with_options(&DemangleOptions::new().no_return_type())
+        .ok()
+}
+
+/// Name shared by the instances of a generic function or template: generic
+/// arguments and parameters are removed, the rest of the path is kept.
+pub fn base_name(name: &str) -> String {
+    let mut base = String::new();
+    let mut chars = name.chars().peekable();
+    let mut depth = 0;
+
+    while let Some(c) = chars.next() {
+        if depth > 0 {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+
+        // operator<, operator(), operator[]... the symbols belong to the name
+        if base.ends_with("operator") {
+            match c {
+                '(' | '[' => {
+                    base.push(c);
+                    base.extend(chars.next());
+                    continue;
+                }
+                _ if "<>=-!+*/%&|^~,".contains(c) => {
+                    base.push(c);
+                    while let Some(next) = chars.next_if(|next| "<>=-!+*/%&|^~,".contains(*next)) {
+                        base.push(next);
+                    }
+                    // Template arguments are separated from the operator: operator<< <char>
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some(' ') && lookahead.next() == Some('<') {
+                        chars.nth(1);
+                        depth = 1;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match c {
+            '<' if base.ends_with("::") => {
+                // Turbofish of Rust: foo::<u32>
+                base.truncate(base.len() - 2);
+                depth = 1;
+            }
+            '<' if base.ends_with(|c: char| c.is_alphanumeric() || c == '_') => depth = 1,
+            '(' => break,
+            _ => base.push(c),
+        }
+    }
+
+    base.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn when_demangling_rust_legacy_symbols_the_hash_shall_be_removed() {
+        assert_eq!(
+            demangle("_ZN4core3ptr13drop_in_place17h1a2b3c4d5e6f7a8bE"),
+            Some("core::ptr::drop_in_place".to_string())
+        );
+    }
+
+    #[test]
+    fn when_demangling_rust_v0_symbols_generic_arguments_shall_be_kept() {
+        assert_eq!(
+            demangle("_RINvCs1234_7mycrate3fooyEB2_"),
+            Some("mycrate::foo::<u64>".to_string())
+        );
+    }
+
+    #[test]
+    fn when_demangling_cpp_symbols_parameters_shall_be_kept() {
+        assert_eq!(
+            demangle("_ZNSt6vectorIiSaIiEE9push_backERKi"),
+            Some("std::vector<int, std::allocator<int> >::push_back(int const&)".to_string())
+        );
+    }
+
+    #[test]
+    fn when_a_name_is_not_mangled_it_shall_not_be_demangled() {
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("square(int)"), None);
+    }
+
+    #[test]
+    fn base_name_shall_not_have_generic_arguments_nor_parameters() {
+        assert_eq!(
+            base_name("std::vector<int, std::allocator<int> >::push_back(int const&)"),
+            "std::vector::push_back"
+        );
+        assert_eq!(base_name("mycrate::foo::<u64>"), "mycrate::foo");
+        assert_eq!(
+            base_name("<alloc::vec::Vec<u8> as core::clone::Clone>::clone"),
+            "<alloc::vec::Vec as core::clone::Clone>::clone"
+        );
+    }
+
+    #[test]
+    fn base_name_shall_keep_operators() {
+        assert_eq!(
+            base_name("Point::operator<(Point const&)"),
+            "Point::operator<"
+        );
+        assert_eq!(
+            base_name("Functor<int>::operator()(int)"),
+            "Functor::operator()"
+        );
+        assert_eq!(base_name("operator<< <char>(std::ostream&)"), "operator<<");
+    }
+}
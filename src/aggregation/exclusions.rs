@@ -0,0 +1,145 @@
+use std::collections::BTreeSet;
+
+use lcov::report::section::Value as SectionValue;
+
+use crate::input::config::ExclusionMarkers;
+
+/// Lines of a source file excluded from the coverage by markers in its
+/// comments. Line numbers start at 1, like in LCOV reports.
+#[derive(Debug, Default, PartialEq)]
+pub struct Exclusions {
+    lines: BTreeSet<u32>,
+    branch_lines: BTreeSet<u32>,
+}
+impl Exclusions {
+    pub fn scan(source_lines: &[String], markers: &ExclusionMarkers) -> Self {
+        let mut exclusions = Exclusions::default();
+        let mut in_excluded_block = false;
+        let mut in_excluded_branch_block = false;
+
+        for (index, text) in source_lines.iter().enumerate() {
+            let line = index as u32 + 1;
+            let contains = |marker: &str| !marker.is_empty() && text.contains(marker);
+
+            in_excluded_block |= contains(&markers.start);
+            in_excluded_branch_block |= contains(&markers.branch_start);
+            if in_excluded_block || contains(&markers.line) {
+                exclusions.lines.insert(line);
+            }
+            if in_excluded_branch_block || contains(&markers.branch_line) {
+                exclusions.branch_lines.insert(line);
+            }
+            in_excluded_block &= !contains(&markers.stop);
+            in_excluded_branch_block &= !contains(&markers.branch_stop);
+        }
+
+        exclusions
+    }
+
+    pub fn is_line_excluded(&self, line: u32) -> bool {
+        self.lines.contains(&line)
+    }
+
+    /// Remove the excluded lines from the section, along with their branches
+    /// and the functions starting on them
+    pub fn apply(&self, section: &mut SectionValue) {
+        section
+            .lines
+            .retain(|key, _| !self.lines.contains(&key.line));
+        section.branches.retain(|key, _| {
+            !self.lines.contains(&key.line) && !self.branch_lines.contains(&key.line)
+        });
+        section.functions.retain(|_, value| {
+            value
+                .start_line
+                .is_none_or(|line| !self.lines.contains(&line))
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::builders::{InsertBranch, InsertLine};
+    use lcov::report::section::function::{Key as FunctionKey, Value as FunctionValue};
+
+    const SOURCE: &str = "int f(int n) {
+    if (n < 0) { // LCOV_EXCL_BR_LINE
+        abort(); // LCOV_EXCL_LINE
+    }
+    // LCOV_EXCL_START
+    if (n > 100) {
+        abort();
+    }
+    // LCOV_EXCL_STOP
+    return n;
+}";
+
+    fn scan(markers: &ExclusionMarkers) -> Exclusions {
+        let lines = SOURCE.lines().map(String::from).collect::<Vec<_>>();
+        Exclusions::scan(&lines, markers)
+    }
+
+    #[test]
+    fn when_scanning_a_source_marked_lines_and_blocks_shall_be_excluded() {
+        let exclusions = scan(&ExclusionMarkers::default());
+        assert_eq!(exclusions.lines, BTreeSet::from([3, 5, 6, 7, 8, 9]));
+        assert_eq!(exclusions.branch_lines, BTreeSet::from([2]));
+    }
+
+    #[test]
+    fn when_markers_are_customized_default_ones_shall_be_ignored() {
+        let exclusions = scan(&ExclusionMarkers {
+            line: "abort".to_string(),
+            start: String::new(),
+            ..Default::default()
+        });
+        assert_eq!(exclusions.lines, BTreeSet::from([3, 7]));
+    }
+
+    #[test]
+    fn when_applying_exclusions_lines_branches_and_functions_shall_be_removed() {
+        let mut section = SectionValue::default()
+            .insert_line(2, 1)
+            .insert_line(3, 0)
+            .insert_line(6, 1)
+            .insert_line(10, 1)
+            .insert_branch(2, 0)
+            .insert_branch(6, 1)
+            .insert_branch(10, 1);
+        for (name, start_line) in [("f", 1), ("g", 6)] {
+            section.functions.insert(
+                FunctionKey {
+                    name: name.to_string(),
+                },
+                FunctionValue {
+                    start_line: Some(start_line),
+                    count: 1,
+                },
+            );
+        }
+
+        scan(&ExclusionMarkers::default()).apply(&mut section);
+
+        assert_eq!(
+            section.lines.keys().map(|key| key.line).collect::<Vec<_>>(),
+            vec![2, 10]
+        );
+        assert_eq!(
+            section
+                .branches
+                .keys()
+                .map(|key| key.line)
+                .collect::<Vec<_>>(),
+            vec![10]
+        );
+        assert_eq!(
+            section
+                .functions
+                .keys()
+                .map(|key| key.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["f"]
+        );
+    }
+}
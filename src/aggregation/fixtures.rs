@@ -1,9 +1,16 @@
-use super::{tested_file::TestedCodeFile, tested_module::TestedModule, tested_root::TestedRoot};
+use super::{
+    baseline::Baseline,
+    tested_file::TestedCodeFile,
+    tested_module::TestedModule,
+    tested_root::{BuildContext, TestedRoot},
+};
 use crate::{
     core::{AggregatedCoverage, AggregatedCoverageCounters},
+    input::{aggregator_input::AggregatorInput, diff::ChangedLines},
     test_utils::builders::{InsertBranch, InsertFunction, InsertLine, InsertSection},
 };
 use lcov::report::section::Value as SectionValue;
+use std::path::PathBuf;
 
 pub struct AggregatedFixtures {}
 impl AggregatedFixtures {
@@ -19,6 +26,7 @@ impl AggregatedFixtures {
             lines: AggregatedCoverageCounters::new(10, 5),
             functions: AggregatedCoverageCounters::new(2, 1),
             branches: AggregatedCoverageCounters::new(3, 2),
+            ..Default::default()
         }
     }
 
@@ -31,6 +39,15 @@ impl AggregatedFixtures {
         }
     }
 
+    /// Build an aggregate with 4 lines, 2 covered, 8 regions, 6 covered
+    pub fn get_file_aggregates_with_regions_8_6() -> AggregatedCoverage {
+        AggregatedCoverage {
+            lines: AggregatedCoverageCounters::new(4, 2),
+            regions: AggregatedCoverageCounters::new(8, 6),
+            ..Default::default()
+        }
+    }
+
     /// Build an aggregate with 3 lines, 1 covered
     pub fn get_file_aggregates_3_1() -> AggregatedCoverage {
         AggregatedCoverage {
@@ -70,4 +87,102 @@ impl AggregatedFixtures {
         let root = TestedRoot::from_original_report(report);
         root
     }
+
+    /// Lines 2 and 3 of main.cpp, and all the lines of module/nested.cpp are
+    /// changed
+    pub fn get_simple_report_with_changed_lines() -> TestedRoot {
+        let report = lcov::Report::new()
+            .insert_section(
+                "main.cpp",
+                SectionValue::default()
+                    .insert_line(1, 1)
+                    .insert_line(2, 0)
+                    .insert_line(3, 1),
+            )
+            .insert_section(
+                "module/nested.cpp",
+                SectionValue::default().insert_line(1, 0).insert_line(2, 0),
+            )
+            .insert_section(
+                "module/other.cpp",
+                SectionValue::default().insert_line(1, 1),
+            );
+        let changed_lines = ChangedLines::parse_unified_diff(
+            "+++ b/main.cpp\n@@ -2 +2,2 @@\n-a\n+b\n+c\n+++ b/module/nested.cpp\n@@ -0,0 +1,2 @@\n+d\n+e\n",
+            &std::env::current_dir().unwrap(),
+        )
+        .unwrap();
+
+        TestedRoot::new(
+            AggregatorInput::new(report).with_name("Test report"),
+            &BuildContext {
+                changed_lines: Some(changed_lines),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// main.cpp lost coverage since the baseline, module/nested.cpp gained
+    /// some, module/new.cpp is not in the baseline, and module/deleted.cpp
+    /// is only in the baseline
+    pub fn get_simple_report_with_baseline() -> TestedRoot {
+        let baseline_report = lcov::Report::new()
+            .insert_section(
+                "main.cpp",
+                SectionValue::default()
+                    .insert_line(1, 1)
+                    .insert_line(2, 1)
+                    .insert_line(3, 1),
+            )
+            .insert_section(
+                "module/nested.cpp",
+                SectionValue::default().insert_line(1, 1).insert_line(2, 0),
+            )
+            .insert_section(
+                "module/deleted.cpp",
+                SectionValue::default().insert_line(1, 1).insert_line(2, 1),
+            );
+        let baseline = Baseline::from_root(&TestedRoot::new(
+            AggregatorInput::new(baseline_report),
+            &BuildContext::default(),
+        ));
+
+        let report = lcov::Report::new()
+            .insert_section(
+                "main.cpp",
+                SectionValue::default()
+                    .insert_line(1, 1)
+                    .insert_line(2, 0)
+                    .insert_line(3, 1),
+            )
+            .insert_section(
+                "module/nested.cpp",
+                SectionValue::default().insert_line(1, 1).insert_line(2, 1),
+            )
+            .insert_section("module/new.cpp", SectionValue::default().insert_line(1, 0));
+
+        TestedRoot::new(
+            AggregatorInput::new(report).with_name("Test report"),
+            &BuildContext {
+                baseline: Some(baseline),
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn get_simple_report_with_regions() -> TestedRoot {
+        let section = SectionValue::default().insert_line(1, 1).insert_line(2, 0);
+        let report = lcov::Report::new().insert_section("lib.rs", section);
+        let regions = [(
+            PathBuf::from("lib.rs"),
+            AggregatedCoverageCounters::new(4, 3),
+        )];
+
+        TestedRoot::new(
+            AggregatorInput::new(report)
+                .with_regions(regions.into_iter().collect())
+                .with_name("Test report"),
+            &BuildContext::default(),
+        )
+    }
 }
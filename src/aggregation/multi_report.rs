@@ -1,6 +1,6 @@
 use crate::core::{AggregatedCoverage, TestedContainer, TestedFile};
 
-use super::{tested_file::TestedCodeFile, tested_root::TestedRoot};
+use super::{baseline::Baseline, tested_file::TestedCodeFile, tested_root::TestedRoot};
 
 pub struct MultiReport {
     name: String,
@@ -22,6 +22,12 @@ impl MultiReport {
         self.reports.push(report);
     }
 
+    /// The reports are compared with the whole baseline, their modules with
+    /// the ones of the baseline holding the same directory
+    pub fn compare_with_baseline(&mut self, baseline: &Baseline) {
+        self.aggregated.baseline = Some(Box::new(baseline.get_root_coverage().clone()));
+    }
+
     pub fn get_multi_report_name(&self) -> &str {
         &self.name
     }
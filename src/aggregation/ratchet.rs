@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::thresholds::ThresholdViolation;
+use crate::core::{AggregatedCoverage, AggregatedCoverageCounters, TestedContainer, WithPath};
+
+/// Percentages of covered lines, functions and branches, rounded like
+/// displayed. They are stored as `f64` to be written as is in the file.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Percentages {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions: Option<f64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branches: Option<f64>,
+}
+
+/// Coverage of the modules of a report, stored in a file checked in along
+/// the sources. The coverage of the next reports shall not go below it.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ratchet {
+    #[serde(default)]
+    pub root: Percentages,
+
+    /// Modules by their path in the report
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, Percentages>,
+}
+
+/// Percentages of a module that went below the stored ones by more than the
+/// tolerance, the stored percentages being the thresholds of the violations
+#[derive(Debug, PartialEq)]
+pub struct CoverageDrop {
+    pub path: String,
+    pub violations: Vec<ThresholdViolation>,
+}
+
+impl Percentages {
+    fn round(counters: &AggregatedCoverageCounters) -> Option<f64> {
+        counters
+            .percentage()
+            .map(|percentage| (percentage as f64 * 100.).round() / 100.)
+    }
+
+    pub fn of(coverage: &AggregatedCoverage) -> Self {
+        Percentages {
+            lines: Self::round(&coverage.lines),
+            functions: Self::round(&coverage.functions),
+            branches: Self::round(&coverage.branches),
+        }
+    }
+
+    fn by_kind(&self) -> [(&'static str, Option<f64>); 3] {
+        [
+            ("Lines", self.lines),
+            ("Functions", self.functions),
+            ("Branches", self.branches),
+        ]
+    }
+
+    /// Kinds of items whose current percentage is below the stored one by
+    /// more than the tolerance. Kinds without any item can't drop.
+    fn check(&self, current: &Self, tolerance: f64) -> Vec<ThresholdViolation> {
+        self.by_kind()
+            .into_iter()
+            .zip(current.by_kind())
+            .filter_map(|((kind, stored), (_, current))| {
+                let (stored, current) = (stored?, current?);
+                (current < stored - tolerance).then_some(ThresholdViolation {
+                    kind,
+                    threshold: stored as f32,
+                    percentage: current as f32,
+                })
+            })
+            .collect()
+    }
+
+    /// Highest of the stored and current percentages of each kind
+    fn raise(&self, current: &Self) -> Self {
+        let raise = |stored: Option<f64>, current: Option<f64>| match (stored, current) {
+            (Some(stored), Some(current)) => Some(stored.max(current)),
+            (stored, current) => current.or(stored),
+        };
+        Percentages {
+            lines: raise(self.lines, current.lines),
+            functions: raise(self.functions, current.functions),
+            branches: raise(self.branches, current.branches),
+        }
+    }
+}
+
+impl Ratchet {
+    pub fn from_root(root: &impl TestedContainer) -> Self {
+        let mut ratchet = Ratchet {
+            root: Percentages::of(root.get_aggregated_coverage()),
+            modules: BTreeMap::new(),
+        };
+        ratchet.add_submodules(root);
+        ratchet
+    }
+
+    fn add_submodules(&mut self, module: &impl TestedContainer) {
+        for submodule in module.get_container_children() {
+            self.modules.insert(
+                submodule.get_path_string(),
+                Percentages::of(submodule.get_aggregated_coverage()),
+            );
+            self.add_submodules(submodule);
+        }
+    }
+
+    /// Root and modules of the current report that dropped below the stored
+    /// coverage by more than the tolerance, in percentage points. Modules
+    /// that are not stored yet can't drop.
+    pub fn check(&self, current: &Ratchet, tolerance: f64) -> Vec<CoverageDrop> {
+        let root = ("".to_string(), &self.root, &current.root);
+        let modules = self.modules.iter().filter_map(|(path, stored)| {
+            current
+                .modules
+                .get(path)
+                .map(|current| (path.clone(), stored, current))
+        });
+        std::iter::once(root)
+            .chain(modules)
+            .filter_map(|(path, stored, current)| {
+                let violations = stored.check(current, tolerance);
+                match violations.is_empty() {
+                    true => None,
+                    false => Some(CoverageDrop { path, violations }),
+                }
+            })
+            .collect()
+    }
+
+    /// Stored coverage raised to the current one where it went up. Modules no
+    /// longer in the report are removed, the new ones are added.
+    pub fn raise(&self, current: &Ratchet) -> Ratchet {
+        Ratchet {
+            root: self.root.raise(&current.root),
+            modules: current
+                .modules
+                .iter()
+                .map(|(path, percentages)| {
+                    let raised = match self.modules.get(path) {
+                        Some(stored) => stored.raise(percentages),
+                        None => percentages.clone(),
+                    };
+                    (path.clone(), raised)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aggregation::fixtures::AggregatedFixtures;
+
+    fn percentages(lines: f64, functions: Option<f64>) -> Percentages {
+        Percentages {
+            lines: Some(lines),
+            functions,
+            branches: None,
+        }
+    }
+
+    #[test]
+    fn when_building_a_ratchet_it_shall_have_the_rounded_percentages_of_all_modules() {
+        let ratchet =
+            Ratchet::from_root(&AggregatedFixtures::get_simple_report_with_changed_lines());
+
+        assert_eq!(ratchet.root, percentages(50., None));
+        assert_eq!(
+            ratchet.modules,
+            BTreeMap::from([("module".to_string(), percentages(33.33, None))])
+        );
+    }
+
+    #[test]
+    fn when_writing_a_ratchet_percentages_shall_be_written_as_displayed() {
+        let ratchet =
+            Ratchet::from_root(&AggregatedFixtures::get_simple_report_with_changed_lines());
+
+        assert_eq!(
+            toml::to_string(&ratchet).unwrap(),
+            "[root]\nlines = 50.0\n\n[modules.module]\nlines = 33.33\n"
+        );
+    }
+
+    #[test]
+    fn when_coverage_dropped_beyond_the_tolerance_it_shall_be_a_drop() {
+        let stored = Ratchet {
+            root: percentages(80., Some(50.)),
+            modules: BTreeMap::from([
+                ("core".to_string(), percentages(90., None)),
+                ("removed".to_string(), percentages(90., None)),
+            ]),
+        };
+        let current = Ratchet {
+            root: percentages(79.5, Some(40.)),
+            modules: BTreeMap::from([
+                ("core".to_string(), percentages(85., None)),
+                ("new".to_string(), percentages(10., None)),
+            ]),
+        };
+
+        assert_eq!(
+            stored.check(&current, 1.),
+            vec![
+                CoverageDrop {
+                    path: "".to_string(),
+                    violations: vec![ThresholdViolation {
+                        kind: "Functions",
+                        threshold: 50.,
+                        percentage: 40.,
+                    }],
+                },
+                CoverageDrop {
+                    path: "core".to_string(),
+                    violations: vec![ThresholdViolation {
+                        kind: "Lines",
+                        threshold: 90.,
+                        percentage: 85.,
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_raising_a_ratchet_it_shall_only_go_up_and_follow_the_modules_of_the_report() {
+        let stored = Ratchet {
+            root: percentages(80., Some(50.)),
+            modules: BTreeMap::from([
+                ("core".to_string(), percentages(90., None)),
+                ("removed".to_string(), percentages(90., None)),
+            ]),
+        };
+        let current = Ratchet {
+            root: percentages(79.5, Some(60.)),
+            modules: BTreeMap::from([
+                ("core".to_string(), percentages(95., Some(20.))),
+                ("new".to_string(), percentages(10., None)),
+            ]),
+        };
+
+        assert_eq!(
+            stored.raise(&current),
+            Ratchet {
+                root: percentages(80., Some(60.)),
+                modules: BTreeMap::from([
+                    ("core".to_string(), percentages(95., Some(20.))),
+                    ("new".to_string(), percentages(10., None)),
+                ]),
+            }
+        );
+    }
+}
@@ -1,7 +1,25 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use crate::core::{AggregatedCoverage, TestedFile};
+use crate::{
+    core::{
+        AggregatedCoverage, AggregatedCoverageCounters, BranchCoverage, FileLinesProvider,
+        FunctionCoverage, FunctionInstance, TestedFile,
+    },
+    file_provider::{GitRevision, SourceRootsLinesProvider},
+    input::config::ExclusionMarkers,
+};
 
+use super::{
+    demangle::{base_name, demangle},
+    exclusions::Exclusions,
+};
+
+use lcov::report::section::branch::Key as BranchKey;
 use lcov::report::section::line::Key as LineKey;
 use lcov::report::section::{Key as SectionKey, Value as SectionValue};
 
@@ -12,6 +30,28 @@ pub struct TestedCodeFile {
     path_relative_to_prefix: String,
     aggregated: AggregatedCoverage,
     section: SectionValue,
+    exclusions: Exclusions,
+    tests: BTreeMap<String, SectionValue>,
+    function_end_lines: BTreeMap<String, u32>,
+    source_root: Option<PathBuf>,
+    source_rev: Option<Rc<GitRevision>>,
+    source_lines: Option<Vec<String>>,
+    changed_lines: BTreeSet<u32>,
+}
+
+/// Source of a tested file, not read again when it already was
+enum TestedFileSource<'a> {
+    Read(&'a [String]),
+    Unread(SourceRootsLinesProvider<'a>),
+}
+
+impl FileLinesProvider for TestedFileSource<'_> {
+    fn get_file_lines(&self) -> Result<Vec<String>, std::io::Error> {
+        match self {
+            TestedFileSource::Read(lines) => Ok(lines.to_vec()),
+            TestedFileSource::Unread(source) => source.get_file_lines(),
+        }
+    }
 }
 
 impl TestedCodeFile {
@@ -23,6 +63,13 @@ impl TestedCodeFile {
             path_relative_to_prefix: String::from(path),
             aggregated: AggregatedCoverage::default(),
             section: SectionValue::default(),
+            exclusions: Exclusions::default(),
+            tests: BTreeMap::new(),
+            function_end_lines: BTreeMap::new(),
+            source_root: None,
+            source_rev: None,
+            source_lines: None,
+            changed_lines: BTreeSet::new(),
         }
     }
 
@@ -32,7 +79,7 @@ impl TestedCodeFile {
         prefix: &str,
         report_key: &str,
     ) -> Self {
-        let path = key.source_file.to_str().unwrap().to_string();
+        let path = key.source_file.to_string_lossy().to_string();
         let file_name = path.split('/').last().unwrap().to_string();
         let aggregated = AggregatedCoverage::from_section(&value);
         let binding = PathBuf::from(prefix);
@@ -41,8 +88,7 @@ impl TestedCodeFile {
         let source_file_parts = key.source_file.components().skip(prefix_parts.len());
         let path_relative_to_prefix = PathBuf::from(report_key)
             .join(PathBuf::from_iter(source_file_parts))
-            .to_str()
-            .unwrap()
+            .to_string_lossy()
             .to_string();
 
         TestedCodeFile {
@@ -51,7 +97,174 @@ impl TestedCodeFile {
             aggregated,
             section: value,
             path_relative_to_prefix,
+            exclusions: Exclusions::default(),
+            tests: BTreeMap::new(),
+            function_end_lines: BTreeMap::new(),
+            source_root: None,
+            source_rev: None,
+            source_lines: None,
+            changed_lines: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_regions(mut self, regions: AggregatedCoverageCounters) -> Self {
+        self.aggregated.regions = regions;
+        self
+    }
+
+    /// Lines excluded by markers in the source are removed from the coverage,
+    /// and kept to be shown. Shall be given before the tests.
+    pub fn with_exclusions(mut self, exclusions: Exclusions) -> Self {
+        exclusions.apply(&mut self.section);
+        self.aggregated = AggregatedCoverage::from_section(&self.section);
+        self.exclusions = exclusions;
+        self
+    }
+
+    /// Exclude the lines marked in the source, read like when rendering it,
+    /// and kept to be rendered without reading it again. A source that can't
+    /// be read has no exclusions, it will be reported missing when rendered.
+    pub fn with_exclusion_markers(mut self, markers: &ExclusionMarkers) -> Self {
+        let source_lines = self.get_source().get_file_lines().ok();
+        self.source_lines = source_lines;
+        let exclusions = self
+            .source_lines
+            .as_deref()
+            .map(|lines| Exclusions::scan(lines, markers))
+            .unwrap_or_default();
+        self.with_exclusions(exclusions)
+    }
+
+    /// Sections of the named tests covering the file, to know the coverage
+    /// contributed by each of them. Exclusions of the file apply to them too.
+    pub fn with_tests(mut self, mut tests: BTreeMap<String, SectionValue>) -> Self {
+        for test_section in tests.values_mut() {
+            self.exclusions.apply(test_section);
         }
+        for (test_name, test_section) in tests.iter() {
+            self.aggregated.tests.insert(
+                test_name.clone(),
+                AggregatedCoverage::from_test_section(&self.section, test_section),
+            );
+        }
+        self.tests = tests;
+        self
+    }
+
+    /// Last line of the functions, when the format of the report gives it
+    pub fn with_function_end_lines(mut self, function_end_lines: BTreeMap<String, u32>) -> Self {
+        self.function_end_lines = function_end_lines;
+        self
+    }
+
+    /// Read the source from another path than the one of the report
+    pub fn with_original_file_path(mut self, path: PathBuf) -> Self {
+        self.path = path.to_string_lossy().to_string();
+        self
+    }
+
+    /// Source root in which the source was found
+    pub fn with_source_root(mut self, source_root: Option<&Path>) -> Self {
+        self.source_root = source_root.map(Path::to_path_buf);
+        self
+    }
+
+    /// Revision of the repository the source is read from, instead of the
+    /// working tree
+    pub fn with_source_rev(mut self, source_rev: Option<Rc<GitRevision>>) -> Self {
+        self.source_rev = source_rev;
+        self
+    }
+
+    /// Lines changed by a diff. Only the tested ones are counted, they shall
+    /// already be removed from the section when excluded.
+    pub fn with_changed_lines(mut self, changed_lines: BTreeSet<u32>) -> Self {
+        let (count, covered_count) = self
+            .section
+            .lines
+            .iter()
+            .filter(|(key, _)| changed_lines.contains(&key.line))
+            .fold((0, 0), |(count, covered), (_, value)| {
+                (count + 1, covered + (value.count > 0) as u32)
+            });
+        self.aggregated.changed_lines = Some(AggregatedCoverageCounters::new(count, covered_count));
+        self.changed_lines = changed_lines;
+        self
+    }
+
+    /// Coverage of the file in the baseline report it is compared with
+    pub fn with_baseline(mut self, baseline: AggregatedCoverage) -> Self {
+        self.aggregated.baseline = Some(Box::new(baseline));
+        self
+    }
+
+    /// The instances share their start line, their span ends with the last
+    /// of them when their end lines are known
+    fn group_instances(&self, name: String, instances: Vec<FunctionCoverage>) -> FunctionCoverage {
+        let first = &instances[0];
+        let end_line = instances
+            .iter()
+            .filter_map(|instance| instance.end_line)
+            .max();
+        let (lines, branches) = match (first.start_line, end_line) {
+            (Some(start_line), Some(end_line)) => {
+                let span = start_line..=end_line.max(start_line);
+                (self.count_lines(span.clone()), self.count_branches(span))
+            }
+            _ => (first.lines.clone(), first.branches.clone()),
+        };
+        FunctionCoverage {
+            name,
+            mangled_name: None,
+            count: instances
+                .iter()
+                .fold(0, |count, instance| count.saturating_add(instance.count)),
+            start_line: first.start_line,
+            end_line,
+            lines,
+            branches,
+            instances: instances
+                .into_iter()
+                .map(|instance| FunctionInstance {
+                    name: instance.name,
+                    mangled_name: instance.mangled_name,
+                    count: instance.count,
+                })
+                .collect(),
+        }
+    }
+
+    fn count_lines(&self, span: RangeInclusive<u32>) -> AggregatedCoverageCounters {
+        let lines = self.section.lines.range(
+            LineKey {
+                line: *span.start(),
+            }..=LineKey { line: *span.end() },
+        );
+        let (count, covered_count) = lines.fold((0, 0), |(count, covered), (_, value)| {
+            (count + 1, covered + (value.count > 0) as u32)
+        });
+        AggregatedCoverageCounters::new(count, covered_count)
+    }
+
+    fn count_branches(&self, span: RangeInclusive<u32>) -> AggregatedCoverageCounters {
+        let first = BranchKey {
+            line: *span.start(),
+            block: 0,
+            branch: 0,
+        };
+        let last = BranchKey {
+            line: *span.end(),
+            block: u32::MAX,
+            branch: u32::MAX,
+        };
+        let (count, covered_count) = self.section.branches.range(first..=last).fold(
+            (0, 0),
+            |(count, covered), (_, value)| {
+                let taken = value.taken.is_some_and(|taken| taken > 0);
+                (count + 1, covered + taken as u32)
+            },
+        );
+        AggregatedCoverageCounters::new(count, covered_count)
     }
 
     pub fn get_file_name(&self) -> &str {
@@ -68,6 +281,21 @@ impl TestedFile for TestedCodeFile {
         PathBuf::from(&self.path)
     }
 
+    fn get_source_root(&self) -> Option<&Path> {
+        self.source_root.as_deref()
+    }
+
+    fn get_source(&self) -> impl FileLinesProvider + '_ {
+        match &self.source_lines {
+            Some(lines) => TestedFileSource::Read(lines),
+            None => TestedFileSource::Unread(SourceRootsLinesProvider::new(
+                self.get_original_file_path(),
+                &[],
+                self.source_rev.as_deref(),
+            )),
+        }
+    }
+
     fn get_aggregated_coverage(&self) -> &AggregatedCoverage {
         &self.aggregated
     }
@@ -79,11 +307,109 @@ impl TestedFile for TestedCodeFile {
             .map_or(None, |value| Some(value.count))
     }
 
-    fn get_functions(&self) -> impl Iterator<Item = (String, u64)> {
+    fn get_tested_lines(&self) -> Vec<u32> {
+        self.section.lines.keys().map(|key| key.line).collect()
+    }
+
+    fn is_line_excluded(&self, line: u32) -> bool {
+        self.exclusions.is_line_excluded(line)
+    }
+
+    fn is_line_changed(&self, line: u32) -> bool {
+        self.changed_lines.contains(&line)
+    }
+
+    fn get_line_tests(&self, line: u32) -> Vec<&str> {
+        self.tests
+            .iter()
+            .filter(|(_, section)| {
+                section
+                    .lines
+                    .get(&LineKey { line })
+                    .is_some_and(|value| value.count > 0)
+            })
+            .map(|(test_name, _)| test_name.as_str())
+            .collect()
+    }
+
+    fn get_line_branches(&self, line: u32) -> Vec<BranchCoverage> {
+        let first = BranchKey {
+            line,
+            block: 0,
+            branch: 0,
+        };
+        let last = BranchKey {
+            line,
+            block: u32::MAX,
+            branch: u32::MAX,
+        };
         self.section
+            .branches
+            .range(first..=last)
+            .map(|(key, value)| BranchCoverage {
+                block: key.block,
+                branch: key.branch,
+                taken: value.taken,
+            })
+            .collect()
+    }
+
+    fn get_functions(&self) -> impl Iterator<Item = FunctionCoverage> {
+        let start_lines = self
+            .section
             .functions
-            .iter()
-            .map(|(key, value)| (key.name.clone(), value.count))
+            .values()
+            .filter_map(|value| value.start_line)
+            .collect::<BTreeSet<_>>();
+
+        // Instances of generic functions and templates are grouped by their
+        // base name and start line
+        let mut groups = BTreeMap::<(String, Option<u32>), Vec<FunctionCoverage>>::new();
+        for (key, value) in self.section.functions.iter() {
+            let end_line = self.function_end_lines.get(&key.name).copied();
+            let span = value.start_line.map(|start_line| {
+                let span_end = end_line.unwrap_or_else(|| {
+                    start_lines
+                        .range(start_line + 1..)
+                        .next()
+                        .map_or(u32::MAX, |next_start_line| next_start_line - 1)
+                });
+                // A range of a BTreeMap can't end before its start
+                start_line..=span_end.max(start_line)
+            });
+            let demangled_name = demangle(&key.name);
+
+            let function = FunctionCoverage {
+                name: demangled_name.clone().unwrap_or_else(|| key.name.clone()),
+                mangled_name: demangled_name.map(|_| key.name.clone()),
+                count: value.count,
+                start_line: value.start_line,
+                end_line,
+                lines: span
+                    .clone()
+                    .map(|span| self.count_lines(span))
+                    .unwrap_or_default(),
+                branches: span
+                    .map(|span| self.count_branches(span))
+                    .unwrap_or_default(),
+                instances: vec![],
+            };
+            let group_name = match function.start_line {
+                Some(_) => base_name(&function.name),
+                None => function.name.clone(),
+            };
+            groups
+                .entry((group_name, function.start_line))
+                .or_default()
+                .push(function);
+        }
+
+        groups
+            .into_iter()
+            .map(|((group_name, _), mut functions)| match functions.len() {
+                1 => functions.remove(0),
+                _ => self.group_instances(group_name, functions),
+            })
     }
 }
 
@@ -100,9 +426,11 @@ impl TestedCodeFile {
 #[cfg(test)]
 mod test {
     use crate::{
-        aggregation::aggregated::assert_aggregated_counters_eq, core::WithPath,
-        test_utils::builders::generate_3_lines_2_covered_section,
+        aggregation::aggregated::assert_aggregated_counters_eq,
+        core::WithPath,
+        test_utils::builders::{generate_3_lines_2_covered_section, InsertBranch, InsertLine},
     };
+    use lcov::report::section::function::{Key as FunctionKey, Value as FunctionValue};
 
     use super::*;
 
@@ -166,4 +494,195 @@ mod test {
         let tested_file = TestedCodeFile::from_section(key, section_value, "", "");
         assert_aggregated_counters_eq(&tested_file.aggregated.lines, 3, 2);
     }
+
+    #[test]
+    fn when_giving_changed_lines_only_the_tested_ones_shall_be_counted() {
+        let key = SectionKey {
+            source_file: std::path::PathBuf::from("path/file.cpp"),
+            test_name: String::from(""),
+        };
+
+        let tested_file =
+            TestedCodeFile::from_section(key, generate_3_lines_2_covered_section(), "", "")
+                .with_changed_lines(BTreeSet::from([2, 3, 10]));
+
+        assert_eq!(
+            tested_file.aggregated.changed_lines,
+            Some(AggregatedCoverageCounters::new(2, 1))
+        );
+        assert!(tested_file.is_line_changed(10));
+        assert!(!tested_file.is_line_changed(1));
+    }
+
+    #[test]
+    fn when_scanning_exclusion_markers_the_source_shall_not_be_read_again_to_be_rendered() {
+        let dir = std::env::temp_dir().join(format!("lcoviz-source-lines-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source_path = dir.join("file.cpp");
+        std::fs::write(&source_path, "int a;\nint b; // LCOV_EXCL_LINE\n").unwrap();
+        let key = SectionKey {
+            source_file: source_path.clone(),
+            test_name: String::from(""),
+        };
+
+        let tested_file =
+            TestedCodeFile::from_section(key, generate_3_lines_2_covered_section(), "", "")
+                .with_original_file_path(source_path)
+                .with_exclusion_markers(&ExclusionMarkers::default());
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert!(tested_file.is_line_excluded(2));
+        assert_eq!(
+            tested_file.get_source().get_file_lines().unwrap(),
+            vec!["int a;", "int b; // LCOV_EXCL_LINE"]
+        );
+    }
+
+    #[test]
+    fn when_listing_functions_their_coverage_shall_be_the_one_of_their_span() {
+        let mut section = SectionValue::default()
+            .insert_line(1, 1)
+            .insert_line(2, 0)
+            .insert_line(3, 1)
+            .insert_line(4, 1)
+            .insert_line(5, 0)
+            .insert_branch(2, 0);
+        for (name, start_line) in [("first", 1), ("second", 3), ("last", 5), ("unknown", 0)] {
+            section.functions.insert(
+                FunctionKey {
+                    name: name.to_string(),
+                },
+                FunctionValue {
+                    start_line: (start_line > 0).then_some(start_line),
+                    count: 1,
+                },
+            );
+        }
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "")
+            .with_function_end_lines(BTreeMap::from([(String::from("second"), 3)]));
+
+        let functions = file
+            .get_functions()
+            .map(|function| (function.name, function.lines, function.branches))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            functions,
+            vec![
+                (
+                    String::from("first"),
+                    AggregatedCoverageCounters::new(2, 1),
+                    AggregatedCoverageCounters::new(1, 0)
+                ),
+                (
+                    String::from("last"),
+                    AggregatedCoverageCounters::new(1, 0),
+                    AggregatedCoverageCounters::default()
+                ),
+                (
+                    String::from("second"),
+                    AggregatedCoverageCounters::new(1, 1),
+                    AggregatedCoverageCounters::default()
+                ),
+                (
+                    String::from("unknown"),
+                    AggregatedCoverageCounters::default(),
+                    AggregatedCoverageCounters::default()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_listing_functions_instances_of_a_template_shall_be_grouped() {
+        let mut section = SectionValue::default();
+        for (name, start_line, count) in [
+            ("_Z3maxIiET_S0_S0_", 2, 3),
+            ("_Z3maxIlET_S0_S0_", 2, 1),
+            ("_ZN4core3ptr13drop_in_place17h1a2b3c4d5e6f7a8bE", 10, 0),
+        ] {
+            section.functions.insert(
+                FunctionKey {
+                    name: name.to_string(),
+                },
+                FunctionValue {
+                    start_line: Some(start_line),
+                    count,
+                },
+            );
+        }
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "");
+
+        let functions = file.get_functions().collect::<Vec<_>>();
+
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name, "core::ptr::drop_in_place");
+        assert_eq!(
+            functions[0].mangled_name.as_deref(),
+            Some("_ZN4core3ptr13drop_in_place17h1a2b3c4d5e6f7a8bE")
+        );
+        assert_eq!(functions[1].name, "max");
+        assert_eq!(functions[1].count, 4);
+        assert_eq!(
+            functions[1].instances,
+            vec![
+                FunctionInstance {
+                    name: String::from("max<int>(int, int)"),
+                    mangled_name: Some(String::from("_Z3maxIiET_S0_S0_")),
+                    count: 3
+                },
+                FunctionInstance {
+                    name: String::from("max<long>(long, long)"),
+                    mangled_name: Some(String::from("_Z3maxIlET_S0_S0_")),
+                    count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_grouping_instances_ending_on_different_lines_their_coverage_shall_be_the_one_of_the_group_span(
+    ) {
+        let mut section = SectionValue::default()
+            .insert_line(2, 1)
+            .insert_line(3, 1)
+            .insert_line(4, 0)
+            .insert_line(5, 0)
+            .insert_branch(4, 0);
+        for name in ["_Z3maxIiET_S0_S0_", "_Z3maxIlET_S0_S0_"] {
+            section.functions.insert(
+                FunctionKey {
+                    name: name.to_string(),
+                },
+                FunctionValue {
+                    start_line: Some(2),
+                    count: 1,
+                },
+            );
+        }
+        let key = SectionKey {
+            source_file: PathBuf::from("file.cpp"),
+            test_name: String::from(""),
+        };
+        let file = TestedCodeFile::from_section(key, section, "", "").with_function_end_lines(
+            BTreeMap::from([
+                (String::from("_Z3maxIiET_S0_S0_"), 3),
+                (String::from("_Z3maxIlET_S0_S0_"), 4),
+            ]),
+        );
+
+        let function = file.get_functions().next().unwrap();
+
+        assert_eq!(function.end_line, Some(4));
+        assert_eq!(function.lines, AggregatedCoverageCounters::new(3, 2));
+        assert_eq!(function.branches, AggregatedCoverageCounters::new(1, 0));
+    }
 }
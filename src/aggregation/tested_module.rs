@@ -1,6 +1,8 @@
+use std::path::Path;
+
 use crate::core::{AggregatedCoverage, TestedContainer, TestedFile};
 
-use super::tested_file::TestedCodeFile;
+use super::{baseline::Baseline, tested_file::TestedCodeFile};
 
 #[derive(Debug, PartialEq, Default)]
 pub struct TestedModule {
@@ -50,6 +52,18 @@ impl TestedModule {
             .unwrap()
             .add_file(path[1..].to_vec(), file);
     }
+
+    /// Compare the module and its submodules with the ones of the baseline
+    /// holding the same directory, if any
+    pub fn compare_with_baseline(&mut self, directory: &Path, baseline: &Baseline) {
+        self.aggregated.baseline = baseline
+            .get_directory_coverage(directory)
+            .cloned()
+            .map(Box::new);
+        for module in self.modules.iter_mut() {
+            module.compare_with_baseline(&directory.join(&module.name), baseline);
+        }
+    }
 }
 
 impl TestedContainer for TestedModule {
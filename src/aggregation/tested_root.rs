@@ -1,13 +1,24 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf, rc::Rc};
 
 use lcov::report::section::{Key as SectionKey, Value as SectionValue};
 
 use crate::{
     core::{AggregatedCoverage, TestedContainer, TestedFile, WithPath},
-    input::aggregator_input::AggregatorInput,
+    file_provider::{GitRevision, SourceRootsLinesProvider},
+    input::{aggregator_input::AggregatorInput, config::ExclusionMarkers, diff::ChangedLines},
 };
 
-use super::{tested_file::TestedCodeFile, tested_module::TestedModule};
+use super::{baseline::Baseline, tested_file::TestedCodeFile, tested_module::TestedModule};
+
+/// What the tested files are built with, besides their report: where their
+/// sources are read from, and what their coverage is compared with
+#[derive(Debug, Default)]
+pub struct BuildContext {
+    pub source_rev: Option<Rc<GitRevision>>,
+    pub exclusion_markers: Option<ExclusionMarkers>,
+    pub changed_lines: Option<ChangedLines>,
+    pub baseline: Option<Baseline>,
+}
 
 #[derive(Debug, PartialEq, Default)]
 pub struct TestedRoot {
@@ -20,7 +31,7 @@ pub struct TestedRoot {
 }
 
 impl TestedRoot {
-    pub fn new(args: AggregatorInput) -> Self {
+    pub fn new(args: AggregatorInput, context: &BuildContext) -> Self {
         let prefix_path = PathBuf::from(args.get_prefix());
         let name = args.get_name();
 
@@ -32,13 +43,32 @@ impl TestedRoot {
             ..Default::default()
         };
 
-        for (section_key, section_value) in args.list_sections() {
-            tree.add_file(section_key, section_value, args.get_prefix());
+        let mut tests = args.list_tests();
+        for (section_key, section_value) in args.list_files() {
+            let file_tests = tests.remove(&section_key.source_file).unwrap_or_default();
+            let file = tree.build_file(&args, context, section_key, section_value, file_tests);
+            tree.add_file(file);
+        }
+        if let Some(baseline) = &context.baseline {
+            tree.compare_with_baseline(baseline);
         }
 
         tree
     }
 
+    /// The root and each of its modules are compared with the ones of the
+    /// baseline holding the same directory, if any
+    fn compare_with_baseline(&mut self, baseline: &Baseline) {
+        self.aggregated.baseline = baseline
+            .get_directory_coverage(&self.prefix)
+            .cloned()
+            .map(Box::new);
+        for module in self.modules.iter_mut() {
+            let directory = self.prefix.join(module.get_module_name());
+            module.compare_with_baseline(&directory, baseline);
+        }
+    }
+
     fn find_module_by_name(&mut self, module_name: &str) -> Option<&mut TestedModule> {
         self.modules
             .iter_mut()
@@ -51,21 +81,90 @@ impl TestedRoot {
         self.modules.last_mut().unwrap()
     }
 
-    fn add_file(&mut self, section_key: SectionKey, section_value: SectionValue, prefix: &str) {
-        let file = TestedCodeFile::from_section(section_key, section_value, prefix, &self.key);
-        let path_relative_to_root = file
-            .get_path_relative_to(&self.get_path())
+    /// Sources are only read to look for exclusion markers when enabled
+    fn build_file(
+        &self,
+        args: &AggregatorInput,
+        context: &BuildContext,
+        section_key: SectionKey,
+        section_value: SectionValue,
+        tests: BTreeMap<String, SectionValue>,
+    ) -> TestedCodeFile {
+        let regions = args.get_regions(&section_key.source_file).cloned();
+        let report_path = section_key.source_file.clone();
+        let function_end_lines = args
+            .get_function_end_lines(&section_key.source_file)
+            .cloned()
+            .unwrap_or_default();
+        let source = SourceRootsLinesProvider::new(
+            args.get_source_path(&section_key.source_file),
+            args.get_source_roots(),
+            context.source_rev.as_deref(),
+        );
+
+        let file =
+            TestedCodeFile::from_section(section_key, section_value, args.get_prefix(), &self.key)
+                .with_original_file_path(source.get_source_path().to_path_buf())
+                .with_source_root(source.get_source_root())
+                .with_source_rev(context.source_rev.clone());
+        let file = match &context.exclusion_markers {
+            Some(markers) => file.with_exclusion_markers(markers),
+            None => file,
+        };
+        let file = file
+            .with_tests(tests)
+            .with_function_end_lines(function_end_lines);
+        // The path of the report is the one of the repository, the diff being
+        // made in it, once located in the source root it was found in
+        let repository_path = match file.get_source_root() {
+            Some(source_root) => source_root.join(&report_path),
+            None => report_path,
+        };
+        let changed_lines = context.changed_lines.as_ref().map(|changed_lines| {
+            changed_lines
+                .get_file_changed_lines(&repository_path)
+                .cloned()
+                .unwrap_or_default()
+        });
+        let file = match changed_lines {
+            Some(changed_lines) => file.with_changed_lines(changed_lines),
+            None => file,
+        };
+        let baseline = context.baseline.as_ref().and_then(|baseline| {
+            baseline.get_file_coverage(
+                &self
+                    .prefix
+                    .join(PathBuf::from_iter(self.get_path_in_root(&file))),
+            )
+        });
+        let file = match baseline {
+            Some(baseline) => file.with_baseline(baseline.clone()),
+            None => file,
+        };
+        match regions {
+            Some(regions) => file.with_regions(regions),
+            None => file,
+        }
+    }
+
+    /// Modules and name of a file in the root
+    fn get_path_in_root(&self, file: &TestedCodeFile) -> Vec<String> {
+        file.get_path_relative_to(&self.get_path())
             .components()
             .filter(|c| c.as_os_str() != "/")
-            .map(|c| c.as_os_str().to_str().unwrap().to_string())
-            .collect::<Vec<String>>();
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn add_file(&mut self, file: TestedCodeFile) {
+        let path_relative_to_root = self.get_path_in_root(&file);
 
         if path_relative_to_root.is_empty() {
             println!("Empty path");
             return;
         }
 
-        self.aggregated.add(&file.get_aggregated_coverage());
+        self.aggregated.add(file.get_aggregated_coverage());
         if path_relative_to_root.len() == 1 {
             self.source_files.push(file);
             return;
@@ -96,6 +195,10 @@ impl TestedRoot {
     pub fn get_key(&self) -> &str {
         &self.key
     }
+
+    pub fn get_prefix(&self) -> &PathBuf {
+        &self.prefix
+    }
 }
 
 impl TestedContainer for TestedRoot {
@@ -151,11 +254,10 @@ impl TestedRoot {
     }
 
     pub fn from_original_report(report: lcov::report::Report) -> Self {
-        TestedRoot::new(AggregatorInput::new(report).with_name("Test report"))
-    }
-
-    pub fn get_prefix(&self) -> &PathBuf {
-        &self.prefix
+        TestedRoot::new(
+            AggregatorInput::new(report).with_name("Test report"),
+            &BuildContext::default(),
+        )
     }
 }
 
@@ -165,16 +267,20 @@ mod test {
 
     use crate::{
         aggregation::aggregated::assert_aggregated_counters_eq,
+        aggregation::baseline::Baseline,
         core::{TestedContainer, TestedFile, WithPath},
+        input::{aggregator_input::AggregatorInput, config::ExclusionMarkers, diff::ChangedLines},
         test_utils::builders::{
-            generate_2_lines_1_covered_section, generate_3_lines_2_covered_section, InsertSection,
+            generate_2_lines_1_covered_section, generate_3_lines_2_covered_section, InsertLine,
+            InsertSection,
         },
     };
 
     use super::{
         super::{tested_file::TestedCodeFile, tested_module::TestedModule},
-        TestedRoot,
+        BuildContext, TestedRoot,
     };
+    use lcov::report::section::Value as SectionValue;
     use lcov::report::Report as LcovReport;
 
     #[test]
@@ -390,4 +496,132 @@ mod test {
             1,
         );
     }
+
+    #[test]
+    fn when_building_tree_with_exclusion_markers_marked_lines_shall_not_be_counted() {
+        let original_report = LcovReport::new().insert_section(
+            "tests/fixtures/my_code.cpp",
+            SectionValue::default()
+                .insert_line(4, 1)
+                .insert_line(5, 0)
+                .insert_line(7, 1),
+        );
+        let markers = ExclusionMarkers {
+            line: "return 1;".to_string(),
+            ..Default::default()
+        };
+
+        let context = BuildContext {
+            exclusion_markers: Some(markers),
+            ..Default::default()
+        };
+
+        let report_tree = TestedRoot::new(AggregatorInput::new(original_report), &context);
+
+        assert_aggregated_counters_eq(&report_tree.get_aggregated_coverage().lines, 2, 2);
+        let file = report_tree.get_container_children().next().unwrap();
+        let file = file.get_container_children().next().unwrap();
+        let file = file.get_code_file_children().next().unwrap();
+        assert!(file.is_line_excluded(5));
+        assert!(!file.is_line_excluded(4));
+    }
+
+    #[test]
+    fn when_building_trees_with_changed_lines_files_shall_be_found_in_their_source_root() {
+        let dir = std::env::temp_dir().join(format!("lcoviz-changed-roots-{}", std::process::id()));
+        for source_root in ["a", "b"] {
+            std::fs::create_dir_all(dir.join(source_root)).unwrap();
+            std::fs::write(dir.join(source_root).join("lib.rs"), "a\nb\n").unwrap();
+        }
+        let context = BuildContext {
+            changed_lines: Some(
+                ChangedLines::parse_unified_diff(
+                    "+++ b/lib.rs\n@@ -0,0 +1 @@\n+a\n+++ b/b/lib.rs\n@@ -0,0 +2 @@\n+b\n",
+                    &dir,
+                )
+                .unwrap(),
+            ),
+            ..Default::default()
+        };
+        let build_tree = |source_root: &str| {
+            let report = LcovReport::new().insert_section(
+                "lib.rs",
+                SectionValue::default().insert_line(1, 1).insert_line(2, 0),
+            );
+            let input = AggregatorInput::new(report).with_source_roots(&[dir.join(source_root)]);
+            TestedRoot::new(input, &context)
+        };
+
+        let tree_a = build_tree("a");
+        let tree_b = build_tree("b");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let file_a = tree_a.get_code_file_children().next().unwrap();
+        assert!(!file_a.is_line_changed(1));
+        assert_aggregated_counters_eq(
+            tree_a
+                .get_aggregated_coverage()
+                .changed_lines
+                .as_ref()
+                .unwrap(),
+            0,
+            0,
+        );
+        let file_b = tree_b.get_code_file_children().next().unwrap();
+        assert!(file_b.is_line_changed(2));
+        assert_aggregated_counters_eq(
+            tree_b
+                .get_aggregated_coverage()
+                .changed_lines
+                .as_ref()
+                .unwrap(),
+            1,
+            0,
+        );
+    }
+
+    #[test]
+    fn when_comparing_with_a_baseline_files_found_in_a_source_root_shall_be_found_by_their_path() {
+        let dir =
+            std::env::temp_dir().join(format!("lcoviz-baseline-roots-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("engine")).unwrap();
+        std::fs::write(dir.join("engine/lib.rs"), "a\nb\n").unwrap();
+        let report = |covered_count| {
+            LcovReport::new().insert_section(
+                "engine/lib.rs",
+                SectionValue::default()
+                    .insert_line(1, covered_count)
+                    .insert_line(2, 0),
+            )
+        };
+        // The baseline is loaded with the settings of the configuration only
+        let baseline = Baseline::from_root(&TestedRoot::new(
+            AggregatorInput::new(report(0)),
+            &BuildContext::default(),
+        ));
+        let input = AggregatorInput::new(report(1)).with_source_roots(std::slice::from_ref(&dir));
+
+        let tree = TestedRoot::new(
+            input,
+            &BuildContext {
+                baseline: Some(baseline),
+                ..Default::default()
+            },
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let module = tree.get_container_children().next().unwrap();
+        let file = module.get_code_file_children().next().unwrap();
+        assert_ne!(file.get_source_root(), None);
+        assert_aggregated_counters_eq(
+            &file
+                .get_aggregated_coverage()
+                .baseline
+                .as_ref()
+                .unwrap()
+                .lines,
+            2,
+            0,
+        );
+    }
 }
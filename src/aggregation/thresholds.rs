@@ -0,0 +1,326 @@
+use globset::{Glob, GlobMatcher};
+
+use crate::{
+    core::{AggregatedCoverage, AggregatedCoverageCounters, TestedContainer, TestedFile, WithPath},
+    input::config::ThresholdRule,
+};
+
+/// Minimal percentages of covered lines, functions and branches
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Thresholds {
+    pub lines: Option<f32>,
+    pub functions: Option<f32>,
+    pub branches: Option<f32>,
+}
+
+/// Percentage of a kind of items below its threshold
+#[derive(Debug, PartialEq)]
+pub struct ThresholdViolation {
+    pub kind: &'static str,
+    pub threshold: f32,
+    pub percentage: f32,
+}
+
+/// Thresholds of the rule applying to a module or a file, that it does not
+/// reach
+#[derive(Debug, PartialEq)]
+pub struct RuleViolations {
+    pub path: String,
+    pub pattern: String,
+    pub violations: Vec<ThresholdViolation>,
+}
+
+/// Threshold rules of the configuration, with their patterns compiled. Like
+/// file filters, a `*` also matches `/`. Modules are matched with a trailing
+/// `/` too, for `core/**` to match the module `core` and its content.
+#[derive(Debug, Default, Clone)]
+pub struct ThresholdRules {
+    rules: Vec<(String, GlobMatcher, Thresholds)>,
+}
+
+/// Outcome of the check of a root against the global thresholds and the
+/// threshold rules
+#[derive(Debug, Default, PartialEq)]
+pub struct ThresholdsCheck {
+    /// Whether any threshold or rule was given
+    pub is_checked: bool,
+    pub violations: Vec<ThresholdViolation>,
+    pub rule_violations: Vec<RuleViolations>,
+}
+
+impl Thresholds {
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_none() && self.functions.is_none() && self.branches.is_none()
+    }
+
+    /// Thresholds that are not reached by the coverage. Kinds without any
+    /// item have nothing to cover, so they reach any threshold.
+    pub fn check(&self, coverage: &AggregatedCoverage) -> Vec<ThresholdViolation> {
+        [
+            ("Lines", self.lines, &coverage.lines),
+            ("Functions", self.functions, &coverage.functions),
+            ("Branches", self.branches, &coverage.branches),
+        ]
+        .into_iter()
+        .filter_map(|(kind, threshold, counters)| Self::check_counters(kind, threshold?, counters))
+        .collect()
+    }
+
+    fn check_counters(
+        kind: &'static str,
+        threshold: f32,
+        counters: &AggregatedCoverageCounters,
+    ) -> Option<ThresholdViolation> {
+        let percentage = counters.percentage()?;
+        match percentage < threshold {
+            true => Some(ThresholdViolation {
+                kind,
+                threshold,
+                percentage,
+            }),
+            false => None,
+        }
+    }
+}
+
+impl ThresholdsCheck {
+    pub fn new(
+        thresholds: &Thresholds,
+        rules: &ThresholdRules,
+        root: &impl TestedContainer,
+    ) -> Self {
+        ThresholdsCheck {
+            is_checked: !thresholds.is_empty() || !rules.is_empty(),
+            violations: thresholds.check(root.get_aggregated_coverage()),
+            rule_violations: rules.check_root(root),
+        }
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.violations.is_empty() && self.rule_violations.is_empty()
+    }
+}
+
+impl ThresholdRules {
+    pub fn new(rules: &[ThresholdRule]) -> Result<Self, String> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Glob::new(&rule.path)
+                    .map(|glob| {
+                        (
+                            rule.path.clone(),
+                            glob.compile_matcher(),
+                            rule.get_thresholds(),
+                        )
+                    })
+                    .map_err(|e| format!("Invalid threshold pattern '{}': {}", rule.path, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ThresholdRules { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Violations of the first rule matching the path of a module or a file,
+    /// if it does not reach its thresholds
+    pub fn check(
+        &self,
+        path: &str,
+        is_dir: bool,
+        coverage: &AggregatedCoverage,
+    ) -> Option<RuleViolations> {
+        let (pattern, _, thresholds) = self.rules.iter().find(|(_, matcher, _)| {
+            matcher.is_match(path) || (is_dir && matcher.is_match(format!("{}/", path)))
+        })?;
+        let violations = thresholds.check(coverage);
+        match violations.is_empty() {
+            true => None,
+            false => Some(RuleViolations {
+                path: path.to_string(),
+                pattern: pattern.clone(),
+                violations,
+            }),
+        }
+    }
+
+    /// Violations of all the modules and files of a root, the root itself
+    /// being checked against the global thresholds only
+    pub fn check_root(&self, root: &impl TestedContainer) -> Vec<RuleViolations> {
+        let mut violations = vec![];
+        if !self.is_empty() {
+            self.check_module_children(root, &mut violations);
+        }
+        violations
+    }
+
+    fn check_module_children(
+        &self,
+        module: &impl TestedContainer,
+        violations: &mut Vec<RuleViolations>,
+    ) {
+        for file in module.get_code_file_children() {
+            violations.extend(self.check(
+                &file.get_path_string(),
+                false,
+                file.get_aggregated_coverage(),
+            ));
+        }
+        for submodule in module.get_container_children() {
+            violations.extend(self.check(
+                &submodule.get_path_string(),
+                true,
+                submodule.get_aggregated_coverage(),
+            ));
+            self.check_module_children(submodule, violations);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aggregation::fixtures::AggregatedFixtures;
+
+    #[test]
+    fn when_no_threshold_is_given_coverage_shall_reach_them() {
+        let thresholds = Thresholds::default();
+
+        assert!(thresholds.is_empty());
+        assert_eq!(
+            thresholds.check(&AggregatedFixtures::get_file_aggregates_10_5()),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn when_checking_coverage_each_threshold_not_reached_shall_be_a_violation() {
+        let thresholds = Thresholds {
+            lines: Some(60.),
+            functions: Some(75.),
+            branches: Some(60.),
+        };
+
+        assert_eq!(
+            thresholds.check(&AggregatedFixtures::get_file_aggregates_10_5()),
+            vec![
+                ThresholdViolation {
+                    kind: "Lines",
+                    threshold: 60.,
+                    percentage: 50.,
+                },
+                ThresholdViolation {
+                    kind: "Functions",
+                    threshold: 75.,
+                    percentage: 50.,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_there_is_no_item_of_a_kind_its_threshold_shall_be_reached() {
+        let thresholds = Thresholds {
+            branches: Some(100.),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            thresholds.check(&AggregatedFixtures::get_file_aggregates_20_10()),
+            vec![]
+        );
+    }
+
+    fn get_rules() -> ThresholdRules {
+        ThresholdRules::new(&[
+            ThresholdRule {
+                path: "module/nested.cpp".to_string(),
+                ..Default::default()
+            },
+            ThresholdRule {
+                path: "module*".to_string(),
+                lines: Some(100.),
+                ..Default::default()
+            },
+            ThresholdRule {
+                path: "*.cpp".to_string(),
+                lines: Some(80.),
+                branches: Some(50.),
+                ..Default::default()
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn when_checking_rules_only_the_first_matching_one_shall_apply() {
+        let rules = get_rules();
+        let coverage = AggregatedFixtures::get_file_aggregates_10_5();
+
+        assert_eq!(rules.check("module/nested.cpp", false, &coverage), None);
+        assert_eq!(
+            rules.check("module/other.cpp", false, &coverage),
+            Some(RuleViolations {
+                path: "module/other.cpp".to_string(),
+                pattern: "module*".to_string(),
+                violations: vec![ThresholdViolation {
+                    kind: "Lines",
+                    threshold: 100.,
+                    percentage: 50.,
+                }],
+            })
+        );
+        assert_eq!(rules.check("lib.rs", false, &coverage), None);
+    }
+
+    #[test]
+    fn when_checking_a_root_all_its_modules_and_files_shall_be_checked() {
+        let root = AggregatedFixtures::get_simple_report_with_changed_lines();
+
+        assert_eq!(
+            get_rules()
+                .check_root(&root)
+                .iter()
+                .map(|rule_violations| rule_violations.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["main.cpp", "module"]
+        );
+    }
+
+    #[test]
+    fn when_a_rule_matches_the_content_of_a_directory_it_shall_match_its_module_too() {
+        let rules = ThresholdRules::new(&[ThresholdRule {
+            path: "module/**".to_string(),
+            lines: Some(100.),
+            ..Default::default()
+        }])
+        .unwrap();
+        let coverage = AggregatedFixtures::get_file_aggregates_10_5();
+
+        assert!(rules.check("module", true, &coverage).is_some());
+        assert!(rules.check("module/nested.cpp", false, &coverage).is_some());
+        assert!(rules.check("module", false, &coverage).is_none());
+        assert!(rules.check("modules", true, &coverage).is_none());
+        assert_eq!(
+            rules
+                .check_root(&AggregatedFixtures::get_simple_report_with_changed_lines())
+                .iter()
+                .map(|rule_violations| rule_violations.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["module", "module/nested.cpp"]
+        );
+    }
+
+    #[test]
+    fn when_a_rule_pattern_is_invalid_it_shall_fail_to_build() {
+        let error = ThresholdRules::new(&[ThresholdRule {
+            path: "core/[".to_string(),
+            ..Default::default()
+        }])
+        .unwrap_err();
+
+        assert!(error.starts_with("Invalid threshold pattern 'core/[': "));
+    }
+}
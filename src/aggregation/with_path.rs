@@ -66,8 +66,10 @@ mod test {
 
     use crate::{
         aggregation::{
-            multi_report::MultiReport, tested_file::TestedCodeFile, tested_module::TestedModule,
-            tested_root::TestedRoot,
+            multi_report::MultiReport,
+            tested_file::TestedCodeFile,
+            tested_module::TestedModule,
+            tested_root::{BuildContext, TestedRoot},
         },
         core::WithPath,
     };
@@ -94,7 +96,9 @@ mod test {
             let tested_root = TestedRoot::new(
                 AggregatorInput::new(lcov::report::Report::new())
                     .with_prefix("path")
+                    .unwrap()
                     .with_key("key"),
+                &BuildContext::default(),
             );
             assert_eq!(tested_root.get_path_string(), "key");
         }
@@ -171,7 +175,9 @@ mod test {
             let tested_root = TestedRoot::new(
                 AggregatorInput::new(lcov::report::Report::new())
                     .with_prefix("path")
+                    .unwrap()
                     .with_key("path"),
+                &BuildContext::default(),
             );
 
             let tested_root_path = PathBuf::from(tested_root.get_prefix());
@@ -238,7 +244,9 @@ mod test {
             let tested_root = TestedRoot::new(
                 AggregatorInput::new(lcov::report::Report::new())
                     .with_prefix("my/prefix/to/project")
+                    .unwrap()
                     .with_key("key"),
+                &BuildContext::default(),
             );
             assert_eq!(tested_root.get_name(), "project");
         }
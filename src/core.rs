@@ -1,6 +1,7 @@
 use std::{
+    collections::BTreeMap,
     error::Error,
-    io::Write,
+    io::{Read, Write},
     path::{Path, PathBuf},
 };
 
@@ -14,7 +15,7 @@ pub trait FileLinesProvider {
     fn get_file_lines(&self) -> Result<Vec<String>, std::io::Error>;
 }
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct AggregatedCoverageCounters {
     pub count: u32,
     pub covered_count: u32,
@@ -33,20 +34,89 @@ impl AggregatedCoverageCounters {
         }
         Some((self.covered_count as f32 / self.count as f32) * 100.0)
     }
+
+    /// Evolution of the percentage since a baseline, rounded like displayed
+    pub fn percentage_delta(&self, baseline: &Self) -> Option<f32> {
+        let delta = self.percentage()? - baseline.percentage()?;
+        Some((delta * 100.0).round() / 100.0)
+    }
 }
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct AggregatedCoverage {
     pub lines: AggregatedCoverageCounters,
     pub functions: AggregatedCoverageCounters,
     pub branches: AggregatedCoverageCounters,
+    pub regions: AggregatedCoverageCounters,
+
+    /// Lines changed by a diff, when the coverage of a diff is reported
+    pub changed_lines: Option<AggregatedCoverageCounters>,
+
+    /// Coverage of the same files in the baseline report, when one is
+    /// compared. Files missing from the baseline have none.
+    pub baseline: Option<Box<AggregatedCoverage>>,
+
+    /// Coverage contributed by each named test (`TN:` records of LCOV),
+    /// out of all the lines, functions and branches
+    pub tests: BTreeMap<String, AggregatedCoverage>,
+}
+
+/// Last line of each function of the source files, for the formats giving it
+pub type FunctionEndLines = BTreeMap<PathBuf, BTreeMap<String, u32>>;
+
+/// A function of a tested file, demangled, with the lines it spans when they
+/// are known.
+/// Its lines and branches are the ones of the span, which ends on the line
+/// before the next function when the end line isn't known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub mangled_name: Option<String>,
+    pub count: u64,
+    pub start_line: Option<u32>,
+    pub end_line: Option<u32>,
+    pub lines: AggregatedCoverageCounters,
+    pub branches: AggregatedCoverageCounters,
+
+    /// Instances of a generic function or template sharing its base name and
+    /// start line, whose hits are summed. Empty for other functions.
+    pub instances: Vec<FunctionInstance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInstance {
+    pub name: String,
+    pub mangled_name: Option<String>,
+    pub count: u64,
+}
+
+/// A branch of a line, `taken` being `None` when the block holding it was
+/// never executed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchCoverage {
+    pub block: u32,
+    pub branch: u32,
+    pub taken: Option<u64>,
+}
+impl BranchCoverage {
+    pub fn is_covered(&self) -> bool {
+        self.taken.is_some_and(|taken| taken > 0)
+    }
 }
 
 pub trait TestedFile: WithPath {
     fn get_original_file_path(&self) -> PathBuf;
+    fn get_source_root(&self) -> Option<&Path>;
+    /// Source of the file, read from where it was found when building it
+    fn get_source(&self) -> impl FileLinesProvider + '_;
     fn get_aggregated_coverage(&self) -> &AggregatedCoverage;
     fn get_line_coverage(&self, line: u32) -> Option<u64>;
-    fn get_functions(&self) -> impl Iterator<Item = (String, u64)>;
+    fn get_tested_lines(&self) -> Vec<u32>;
+    fn is_line_excluded(&self, line: u32) -> bool;
+    fn is_line_changed(&self, line: u32) -> bool;
+    fn get_line_tests(&self, line: u32) -> Vec<&str>;
+    fn get_line_branches(&self, line: u32) -> Vec<BranchCoverage>;
+    fn get_functions(&self) -> impl Iterator<Item = FunctionCoverage>;
 }
 
 pub trait TestedContainer: WithPath {
@@ -55,6 +125,13 @@ pub trait TestedContainer: WithPath {
     fn get_code_file_children(&self) -> impl Iterator<Item = &impl TestedFile>;
 }
 
+/// Page of a file, telling whether its source could be read to render it
+#[derive(Debug, PartialEq)]
+pub struct RenderedFile {
+    pub content: String,
+    pub is_source_missing: bool,
+}
+
 pub trait Renderer {
     fn render_module_coverage_details(
         &self,
@@ -66,7 +143,7 @@ pub trait Renderer {
         root: &impl WithPath,
         file: &impl TestedFile,
         file_provider: &impl FileLinesProvider,
-    ) -> String;
+    ) -> RenderedFile;
     fn get_required_resources(
         &self,
         root: &impl TestedContainer,
@@ -74,7 +151,9 @@ pub trait Renderer {
 }
 
 pub trait Exporter {
-    fn render_root(self) -> ();
+    /// Render the report, returning the paths of the sources that couldn't be
+    /// read
+    fn render_root(self) -> Vec<PathBuf>;
 }
 
 pub trait WithPath {
@@ -111,7 +190,10 @@ pub trait LinksComputer {
 pub trait FileSystem {
     fn create_dir_all(&self, path: &Path) -> Result<(), Box<dyn Error>>;
     fn write_all(&self, path: &Path, content: &str) -> Result<(), Box<dyn Error>>;
-    fn read_to_string(&self, path: &Path) -> Result<String, Box<dyn Error>>;
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn Error>>;
+    fn read_stdin(&self) -> Result<Vec<u8>, Box<dyn Error>>;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn list_files_recursively(&self, path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>>;
 }
 pub struct LocalFileSystem;
 impl FileSystem for LocalFileSystem {
@@ -126,7 +208,60 @@ impl FileSystem for LocalFileSystem {
         Ok(())
     }
 
-    fn read_to_string(&self, path: &Path) -> Result<String, Box<dyn Error>> {
-        Ok(std::fs::read_to_string(path)?)
+    fn read(&self, path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(std::fs::read(path)?)
+    }
+
+    fn read_stdin(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut content = Vec::new();
+        std::io::stdin().read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    /// Symlinks to directories are not followed, as they may loop
+    fn list_files_recursively(&self, path: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let entry_path = entry.path();
+            if entry.file_type()?.is_symlink() && entry_path.is_dir() {
+                continue;
+            }
+            if entry_path.is_dir() {
+                files.extend(self.list_files_recursively(&entry_path)?);
+            } else {
+                files.push(entry_path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn when_listing_files_symlinks_to_directories_shall_not_be_followed() {
+        let dir = std::env::temp_dir().join(format!("lcoviz-symlinks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("tests")).unwrap();
+        std::fs::write(dir.join("tests/lcov.info"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("tests/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("tests/lcov.info"), dir.join("link.info")).unwrap();
+
+        let files = LocalFileSystem.list_files_recursively(&dir).unwrap();
+
+        assert_eq!(
+            files,
+            vec![dir.join("link.info"), dir.join("tests/lcov.info")]
+        );
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,219 @@
+use std::{
+    fmt::Debug,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use git2::{ObjectType, Oid, Repository, TreeEntry};
+
+use crate::core::FileLinesProvider;
+
+use super::normalize_path;
+
+/// Commit of the local repository the sources are read from, instead of the
+/// working tree. Paths are resolved like in the working tree: absolute ones
+/// shall be in the repository, relative ones are relative to the working
+/// directory.
+pub struct GitRevision {
+    repository: Repository,
+    revision: String,
+    tree: Oid,
+    working_dir: PathBuf,
+}
+
+impl GitRevision {
+    /// Open the revision (sha, tag, branch...) in the repository containing
+    /// the working directory
+    pub fn open(revision: &str) -> Result<Self, String> {
+        let working_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+        Self::open_from(&working_dir, revision)
+    }
+
+    fn open_from(working_dir: &Path, revision: &str) -> Result<Self, String> {
+        let repository = Repository::discover(working_dir)
+            .map_err(|e| format!("No git repository found: {}", e.message()))?;
+        let tree = repository
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|e| format!("Invalid revision {}: {}", revision, e.message()))?
+            .id();
+        let working_dir = working_dir.canonicalize().map_err(|e| e.to_string())?;
+
+        Ok(GitRevision {
+            repository,
+            revision: revision.to_string(),
+            tree,
+            working_dir,
+        })
+    }
+
+    /// Path of a source relative to the root of the repository, `..` being
+    /// resolved without looking at the working tree
+    fn get_path_in_tree(&self, path: &Path) -> Option<PathBuf> {
+        let workdir = self.repository.workdir()?.canonicalize().ok()?;
+        normalize_path(&self.working_dir.join(path))
+            .strip_prefix(workdir)
+            .ok()
+            .map(Path::to_path_buf)
+    }
+
+    fn not_found(&self) -> Error {
+        Error::new(
+            ErrorKind::NotFound,
+            format!("not found in revision {}", self.revision),
+        )
+    }
+
+    fn get_entry(&self, path: &Path) -> Result<TreeEntry<'static>, Error> {
+        let path_in_tree = self
+            .get_path_in_tree(path)
+            .ok_or_else(|| self.not_found())?;
+        let tree = self
+            .repository
+            .find_tree(self.tree)
+            .map_err(|e| Error::other(e.message().to_string()))?;
+        tree.get_path(&path_in_tree).map_err(|_| self.not_found())
+    }
+
+    fn read_blob(&self, path: &Path) -> Result<Vec<u8>, Error> {
+        let blob = self
+            .get_entry(path)?
+            .to_object(&self.repository)
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|_| self.not_found())?;
+        Ok(blob.content().to_vec())
+    }
+
+    /// Only the entry of the file is looked for, its content is not loaded
+    pub fn has_file(&self, path: &Path) -> bool {
+        self.get_entry(path)
+            .is_ok_and(|entry| entry.kind() == Some(ObjectType::Blob))
+    }
+}
+
+impl Debug for GitRevision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GitRevision")
+            .field("revision", &self.revision)
+            .field("tree", &self.tree)
+            .finish()
+    }
+}
+
+/// Revisions are the same when they name the same tree, the repository
+/// can't be compared
+impl PartialEq for GitRevision {
+    fn eq(&self, other: &Self) -> bool {
+        self.revision == other.revision && self.tree == other.tree
+    }
+}
+
+pub struct GitRevisionLinesProvider<'a> {
+    revision: &'a GitRevision,
+    file_path: PathBuf,
+}
+
+impl<'a> GitRevisionLinesProvider<'a> {
+    pub fn new(revision: &'a GitRevision, file_path: PathBuf) -> Self {
+        GitRevisionLinesProvider {
+            revision,
+            file_path,
+        }
+    }
+}
+
+impl FileLinesProvider for GitRevisionLinesProvider<'_> {
+    fn get_file_lines(&self) -> Result<Vec<String>, Error> {
+        let content = self.revision.read_blob(&self.file_path)?;
+        Ok(String::from_utf8_lossy(&content)
+            .lines()
+            .map(|s| s.to_string())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Repository with a first commit of `src/main.rs`, modified afterward in
+    /// the working tree
+    fn init_repository(dir: &Path) -> String {
+        let repository = Repository::init(dir).unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {\n    run();\n}\n").unwrap();
+
+        let mut index = repository.index().unwrap();
+        index.add_path(Path::new("src/main.rs")).unwrap();
+        let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("me", "me@example.com").unwrap();
+        let commit = repository
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+
+        std::fs::write(dir.join("src/main.rs"), "// Moved on\nfn main() {}\n").unwrap();
+        commit.to_string()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lcoviz-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn when_reading_a_file_from_a_revision_it_shall_have_its_content_at_this_revision() {
+        let dir = temp_dir("git-revision-content");
+        let sha = init_repository(&dir);
+        let revision = GitRevision::open_from(&dir, &sha).unwrap();
+
+        let lines = GitRevisionLinesProvider::new(&revision, PathBuf::from("src/main.rs"))
+            .get_file_lines()
+            .unwrap();
+
+        assert_eq!(lines, vec!["fn main() {", "    run();", "}"]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn when_reading_files_from_a_revision_absolute_and_relative_paths_shall_be_resolved() {
+        let dir = temp_dir("git-revision-paths");
+        init_repository(&dir);
+        let revision = GitRevision::open_from(&dir.join("src"), "HEAD").unwrap();
+
+        assert!(revision.has_file(&dir.join("src/main.rs")));
+        assert!(revision.has_file(Path::new("main.rs")));
+        assert!(revision.has_file(Path::new("../src/./main.rs")));
+        assert!(!revision.has_file(Path::new("src/main.rs")));
+        assert!(!revision.has_file(Path::new("../src")));
+        assert!(!revision.has_file(Path::new("/elsewhere/main.rs")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn when_a_file_is_not_in_the_revision_it_shall_fail_to_be_read() {
+        let dir = temp_dir("git-revision-missing");
+        init_repository(&dir);
+        let revision = GitRevision::open_from(&dir, "HEAD").unwrap();
+
+        let error = GitRevisionLinesProvider::new(&revision, PathBuf::from("src/lib.rs"))
+            .get_file_lines()
+            .unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(error.to_string(), "not found in revision HEAD");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn when_opening_an_unknown_revision_it_shall_fail() {
+        let dir = temp_dir("git-revision-unknown");
+        init_repository(&dir);
+
+        let error = GitRevision::open_from(&dir, "v9.9.9").unwrap_err();
+
+        assert!(error.starts_with("Invalid revision v9.9.9: "));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,26 @@
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 use crate::core::FileLinesProvider;
 
+mod git_revision;
+pub use git_revision::{GitRevision, GitRevisionLinesProvider};
+
+/// Resolve `.` and `..` in a path without looking at the file system, as the
+/// files may not exist anymore
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized_path = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized_path.pop();
+            }
+            Component::CurDir => {}
+            _ => normalized_path.push(component),
+        }
+    }
+    normalized_path
+}
+
 pub struct LocalFileLinesProvider {
     file_path: PathBuf,
 }
@@ -20,6 +39,68 @@ impl FileLinesProvider for LocalFileLinesProvider {
     }
 }
 
+/// Source with a relative path, looked for in the working directory first,
+/// then in each of the source roots in order. With a git revision, the files
+/// are looked for in this revision instead of the working tree.
+pub struct SourceRootsLinesProvider<'a> {
+    source_path: PathBuf,
+    source_root: Option<&'a Path>,
+    source_rev: Option<&'a GitRevision>,
+}
+
+impl<'a> SourceRootsLinesProvider<'a> {
+    /// The source is looked for once, when the provider is built
+    pub fn new(
+        file_path: PathBuf,
+        source_roots: &'a [PathBuf],
+        source_rev: Option<&'a GitRevision>,
+    ) -> Self {
+        let is_file = |path: &Path| match source_rev {
+            Some(source_rev) => source_rev.has_file(path),
+            None => path.is_file(),
+        };
+        let source_root =
+            match file_path.is_absolute() || source_roots.is_empty() || is_file(&file_path) {
+                true => None,
+                false => source_roots
+                    .iter()
+                    .find(|root| is_file(&root.join(&file_path)))
+                    .map(PathBuf::as_path),
+            };
+        let source_path = match source_root {
+            Some(root) => root.join(&file_path),
+            None => file_path,
+        };
+
+        SourceRootsLinesProvider {
+            source_path,
+            source_root,
+            source_rev,
+        }
+    }
+
+    /// Root in which the source was found, if it was not readable as is
+    pub fn get_source_root(&self) -> Option<&'a Path> {
+        self.source_root
+    }
+
+    /// Path of the source, to read it later on
+    pub fn get_source_path(&self) -> &Path {
+        &self.source_path
+    }
+}
+
+impl FileLinesProvider for SourceRootsLinesProvider<'_> {
+    fn get_file_lines(&self) -> Result<Vec<String>, std::io::Error> {
+        match self.source_rev {
+            Some(source_rev) => {
+                GitRevisionLinesProvider::new(source_rev, self.source_path.clone()).get_file_lines()
+            }
+            None => LocalFileLinesProvider::new(self.source_path.clone()).get_file_lines(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,4 +132,46 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn when_a_source_is_in_a_source_root_the_first_root_having_it_shall_be_used() {
+        let source_roots = vec![
+            PathBuf::from("tests/missing"),
+            PathBuf::from("tests/fixtures"),
+            PathBuf::from("tests"),
+        ];
+        let file = SourceRootsLinesProvider::new(PathBuf::from("my_code.cpp"), &source_roots, None);
+
+        assert_eq!(file.get_source_root(), Some(Path::new("tests/fixtures")));
+        assert_eq!(
+            file.get_source_path(),
+            Path::new("tests/fixtures/my_code.cpp")
+        );
+        assert_eq!(file.get_file_lines().unwrap()[0], "#include <iostream>");
+    }
+
+    #[test]
+    fn when_a_source_is_readable_from_the_working_directory_no_root_shall_be_used() {
+        let source_roots = vec![PathBuf::from("tests")];
+        let file = SourceRootsLinesProvider::new(
+            PathBuf::from("tests/fixtures/my_code.cpp"),
+            &source_roots,
+            None,
+        );
+
+        assert_eq!(file.get_source_root(), None);
+        assert_eq!(
+            file.get_source_path(),
+            Path::new("tests/fixtures/my_code.cpp")
+        );
+    }
+
+    #[test]
+    fn when_a_source_is_in_no_root_it_shall_fail_to_be_read() {
+        let source_roots = vec![PathBuf::from("tests")];
+        let file = SourceRootsLinesProvider::new(PathBuf::from("missing.cpp"), &source_roots, None);
+
+        assert_eq!(file.get_source_root(), None);
+        assert!(file.get_file_lines().is_err());
+    }
 }
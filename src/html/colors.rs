@@ -4,6 +4,24 @@ pub fn render_optional_percentage(percentage: Option<f32>) -> String {
         .unwrap_or("-".to_string())
 }
 
+/// Evolution of a percentage since a baseline, nothing when it is unknown
+pub fn render_optional_percentage_delta(delta: Option<f32>) -> String {
+    match delta {
+        Some(delta) if delta > 0. => format!("▲ {:.2}%", delta),
+        Some(delta) if delta < 0. => format!("▼ {:.2}%", -delta),
+        Some(_) => "=".to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn get_percentage_delta_class(delta: Option<f32>) -> &'static str {
+    match delta {
+        Some(delta) if delta > 0. => "delta-up",
+        Some(delta) if delta < 0. => "delta-down",
+        _ => "delta-none",
+    }
+}
+
 pub fn get_percentage_class(prefix: &str, percentage: &Option<f32>) -> String {
     percentage
         .map(|p| {
@@ -32,6 +50,14 @@ mod tests {
         assert_eq!(render_optional_percentage(None), "-");
     }
 
+    #[test]
+    fn render_optional_percentage_delta_shall_point_to_the_evolution() {
+        assert_eq!(render_optional_percentage_delta(Some(2.5)), "▲ 2.50%");
+        assert_eq!(render_optional_percentage_delta(Some(-10.)), "▼ 10.00%");
+        assert_eq!(render_optional_percentage_delta(Some(0.)), "=");
+        assert_eq!(render_optional_percentage_delta(None), "");
+    }
+
     #[test]
     fn get_percentage_class_should_return_none() {
         assert_eq!(get_percentage_class("test", &None), "test-none");
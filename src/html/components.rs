@@ -1,10 +1,15 @@
-use htmlescape::encode_minimal;
+use htmlescape::{encode_attribute, encode_minimal};
 
 use crate::{core::LinkPayload, html::colors::get_percentage_class};
 
 pub trait ToHtml {
     fn to_html(&self) -> String;
 }
+impl<T: ToHtml + ?Sized> ToHtml for Box<T> {
+    fn to_html(&self) -> String {
+        self.as_ref().to_html()
+    }
+}
 
 pub struct Text {
     level: u8,
@@ -132,6 +137,7 @@ impl ToHtml for Pre {
 pub struct Div<'a> {
     class_names: Vec<String>,
     id: Option<String>,
+    title: Option<String>,
     children: Vec<Box<dyn ToHtml + 'a>>,
 }
 impl<'a> Div<'a> {
@@ -140,6 +146,7 @@ impl<'a> Div<'a> {
             class_names: Vec::new(),
             children: Vec::new(),
             id: None,
+            title: None,
         }
     }
     pub fn with_class(mut self, class: &str) -> Self {
@@ -150,6 +157,10 @@ impl<'a> Div<'a> {
         self.id = Some(id.to_string());
         self
     }
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
     pub fn with_child(mut self, child: impl ToHtml + 'a) -> Self {
         self.children.push(Box::new(child));
         self
@@ -178,20 +189,29 @@ impl<'a> ToHtml for Div<'a> {
             Some(id) => format!(" id=\"{}\"", id),
             None => String::new(),
         };
+        let title_attr = match &self.title {
+            Some(title) => format!(" title=\"{}\"", encode_attribute(title)),
+            None => String::new(),
+        };
         let children_html: String = self.children.iter().map(|c| c.to_html()).collect();
 
-        format!("<div{}{}>{}</div>", class_attr, id_attr, children_html)
+        format!(
+            "<div{}{}{}>{}</div>",
+            class_attr, id_attr, title_attr, children_html
+        )
     }
 }
 
 pub struct Row<'a> {
     class_names: Vec<String>,
+    id: Option<String>,
     cells: Vec<Box<dyn ToHtml + 'a>>,
 }
 impl<'a> Row<'a> {
     pub fn new() -> Self {
         Row {
             class_names: Vec::new(),
+            id: None,
             cells: Vec::new(),
         }
     }
@@ -199,6 +219,10 @@ impl<'a> Row<'a> {
         self.class_names.push(class.to_string());
         self
     }
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
     pub fn with_cell(mut self, cell: impl ToHtml + 'a) -> Self {
         self.cells.push(Box::new(cell));
         self
@@ -229,13 +253,17 @@ impl<'a> ToHtml for Row<'a> {
             0 => String::new(),
             _ => format!(" class=\"{}\"", self.class_names.join(" ")),
         };
+        let id_attr = match &self.id {
+            Some(id) => format!(" id=\"{}\"", id),
+            None => String::new(),
+        };
         let cells_html: String = self
             .cells
             .iter()
             .map(|c| format!("<td>{}</td>", c.to_html()))
             .collect();
 
-        format!("<tr{}>{}</tr>", class_attr, cells_html)
+        format!("<tr{}{}>{}</tr>", class_attr, id_attr, cells_html)
     }
 }
 impl<'a> ToHtml for Table<'a> {
@@ -1,89 +1,455 @@
 use lcov::{
-    report::section::{Key as SectionKey, Value as SectionValue},
-    Reader, Report as LcovReport,
+    reader::Error as ReadError,
+    report::{
+        section::{Key as SectionKey, Value as SectionValue},
+        ParseError,
+    },
+    Reader, Record, Report as LcovReport,
+};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::{Path, PathBuf},
 };
-use std::collections::{BTreeMap, HashMap};
 
-use crate::core::FileSystem;
+use crate::core::{AggregatedCoverageCounters, FileSystem, FunctionEndLines};
 
-use super::config::Input;
+use super::{
+    compression::decompress,
+    config::{Input, InputFormat, PathMapping, STDIN_PATH},
+    errors::InputError,
+    filters::{FileFilter, RemovedFiles},
+    formats::{cobertura, gcov_json, go_cover, istanbul, jacoco, llvm_cov},
+};
 
+#[derive(Debug)]
 pub struct AggregatorInput {
     report: lcov::report::Report,
+    regions: BTreeMap<PathBuf, AggregatedCoverageCounters>,
+    function_end_lines: FunctionEndLines,
     prefix: String,
     name: Option<String>,
     key: String,
+    path_mappings: Vec<PathMapping>,
+    source_roots: Vec<PathBuf>,
+    removed_files: RemovedFiles,
 }
 
 impl AggregatorInput {
     pub fn new(report: LcovReport) -> Self {
         Self {
             report,
+            regions: BTreeMap::new(),
+            function_end_lines: FunctionEndLines::new(),
             prefix: String::new(),
             key: String::new(),
             name: None,
+            path_mappings: vec![],
+            source_roots: vec![],
+            removed_files: RemovedFiles::new(),
         }
     }
 
-    pub fn from_config_input(input: Input, fs: &impl FileSystem) -> Self {
-        let report_content = fs.read_to_string(&input.path).unwrap();
-        let reader = Reader::new(report_content.as_bytes());
-        let report = LcovReport::from_reader(reader).unwrap();
-        let aggregator_input = Self::new(report);
-        let aggregator_input = match input.prefix {
-            Some(prefix) => aggregator_input.with_prefix(prefix.to_str().unwrap()),
-            None => aggregator_input.with_longest_prefix(),
+    /// Merge inputs loaded from the configuration into a single one. The prefix
+    /// is the first one given by an input, if any, and the name is the one of
+    /// their group.
+    fn from_loaded_inputs(inputs: &[Input], loaded_inputs: Vec<Self>) -> Result<Self, InputError> {
+        let prefix = inputs.iter().find_map(|input| input.prefix.clone());
+        let name = inputs
+            .iter()
+            .find_map(|input| input.group.clone().or_else(|| input.name.clone()));
+
+        loaded_inputs
+            .into_iter()
+            .fold(Self::new(LcovReport::new()), Self::merge)
+            .with_config_prefix_and_name(prefix, name)
+            .map_err(|e| InputError::prefix(&inputs[0], e))
+    }
+
+    fn load(input: &Input, fs: &impl FileSystem) -> Result<Self, InputError> {
+        let loaded_input = match fs.is_dir(&input.path) {
+            true => Self::parse_gcov_json_directory(input, fs)?,
+            false => {
+                let report_content = Self::read_report(&input.path, fs)
+                    .map_err(|e| InputError::read(input, input.path.clone(), e))?;
+                let format = input
+                    .format
+                    .unwrap_or_else(|| InputFormat::detect(&report_content));
+                Self::parse_report(&report_content, format)
+                    .map_err(|(line, e)| InputError::parse(input, line, e))?
+            }
         };
+        let file_filter = FileFilter::new(&input.include, &input.exclude)
+            .map_err(|e| InputError::filter(input, e))?;
+        Ok(loaded_input
+            .with_path_mappings(&input.path_mappings)
+            .with_source_roots(&input.source_roots)
+            .with_file_filter(&file_filter))
+    }
+
+    /// Remove the tested files rejected by the filter, counting them for each
+    /// of the patterns. Files are matched on their displayed path.
+    fn with_file_filter(mut self, file_filter: &FileFilter) -> Self {
+        if file_filter.is_empty() {
+            return self;
+        }
+
+        // A file may have a section for each test, it is counted once
+        let removed_files = &mut self.removed_files;
+        let mut removed_paths = BTreeSet::new();
+        self.report.sections.retain(|key, _| {
+            match file_filter.get_removing_filter(&key.source_file) {
+                Some(filter) => {
+                    if removed_paths.insert(key.source_file.clone()) {
+                        *removed_files.entry(filter.to_string()).or_default() += 1;
+                    }
+                    false
+                }
+                None => true,
+            }
+        });
+        for path in removed_paths {
+            self.regions.remove(&path);
+            self.function_end_lines.remove(&path);
+        }
+        self
+    }
 
-        match input.name {
+    pub fn get_removed_files(&self) -> &RemovedFiles {
+        &self.removed_files
+    }
+
+    /// Source files are renamed right away by the mappings applying to the
+    /// display, so that the prefix is computed on their new paths. The other
+    /// mappings are kept to find the sources later on.
+    fn with_path_mappings(self, path_mappings: &[PathMapping]) -> Self {
+        let display_mappings = path_mappings
+            .iter()
+            .filter(|mapping| mapping.display)
+            .collect::<Vec<_>>();
+        let map_path = |path: PathBuf| {
+            PathMapping::map_path(display_mappings.iter().copied(), &path).unwrap_or(path)
+        };
+
+        // Two source files may be mapped to the same one, so they are merged
+        let mut report = LcovReport::new();
+        for (key, value) in self.report.sections {
+            let mut section = LcovReport::new();
+            section.sections.insert(
+                SectionKey {
+                    test_name: key.test_name,
+                    source_file: map_path(key.source_file),
+                },
+                value,
+            );
+            report.merge_lossy(section);
+        }
+        let regions = self
+            .regions
+            .into_iter()
+            .map(|(source_file, regions)| (map_path(source_file), regions))
+            .collect();
+        let mut function_end_lines = FunctionEndLines::new();
+        for (source_file, end_lines) in self.function_end_lines {
+            function_end_lines
+                .entry(map_path(source_file))
+                .or_default()
+                .extend(end_lines);
+        }
+
+        AggregatorInput {
+            report,
+            regions,
+            function_end_lines,
+            path_mappings: path_mappings
+                .iter()
+                .filter(|mapping| !mapping.display)
+                .cloned()
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Path where the source of a tested file shall be looked for
+    pub fn get_source_path(&self, source_file: &Path) -> PathBuf {
+        PathMapping::map_path(&self.path_mappings, source_file)
+            .unwrap_or_else(|| source_file.to_path_buf())
+    }
+
+    /// Directories where the sources with a relative path are looked for, in
+    /// order, when they can't be read from the working directory
+    pub fn with_source_roots(self, source_roots: &[PathBuf]) -> Self {
+        AggregatorInput {
+            source_roots: source_roots.to_vec(),
+            ..self
+        }
+    }
+
+    pub fn get_source_roots(&self) -> &[PathBuf] {
+        &self.source_roots
+    }
+
+    fn with_config_prefix_and_name(
+        self,
+        prefix: Option<PathBuf>,
+        name: Option<String>,
+    ) -> Result<Self, String> {
+        let aggregator_input = match prefix {
+            Some(prefix) => self.with_prefix(&prefix.to_string_lossy())?,
+            None => self.with_longest_prefix(),
+        };
+
+        Ok(match name {
             Some(name) => aggregator_input.with_name(&name),
             None => aggregator_input,
+        })
+    }
+
+    /// Merge the coverage of another input into this one: hits of lines,
+    /// functions and branches of the same file are summed.
+    /// Regions can't be merged without knowing which ones are covered in each
+    /// input, so the best covered input wins.
+    pub fn merge(mut self, other: AggregatorInput) -> Self {
+        self.report.merge_lossy(other.report);
+        for (source_file, other_regions) in other.regions {
+            let regions = self.regions.entry(source_file).or_default();
+            regions.count = regions.count.max(other_regions.count);
+            regions.covered_count = regions.covered_count.max(other_regions.covered_count);
+        }
+        for (source_file, end_lines) in other.function_end_lines {
+            self.function_end_lines
+                .entry(source_file)
+                .or_default()
+                .extend(end_lines);
+        }
+        for (filter, count) in other.removed_files {
+            *self.removed_files.entry(filter).or_default() += count;
         }
+        for path_mapping in other.path_mappings {
+            if !self.path_mappings.contains(&path_mapping) {
+                self.path_mappings.push(path_mapping);
+            }
+        }
+        for source_root in other.source_roots {
+            if !self.source_roots.contains(&source_root) {
+                self.source_roots.push(source_root);
+            }
+        }
+        self
+    }
+
+    /// Write the (possibly merged) coverage of this input as a LCOV tracefile
+    pub fn to_lcov_string(&self) -> String {
+        self.report
+            .clone()
+            .into_records()
+            .map(|record| format!("{}\n", record))
+            .collect()
     }
 
+    /// Sections of the report, the ones of the same source file being merged
+    /// whatever the test they come from
+    pub fn list_files(&self) -> BTreeMap<SectionKey, SectionValue> {
+        let mut files = LcovReport::new();
+        for (key, value) in self.report.sections.iter() {
+            let mut file = LcovReport::new();
+            file.sections.insert(
+                SectionKey {
+                    test_name: String::new(),
+                    source_file: key.source_file.clone(),
+                },
+                value.clone(),
+            );
+            files.merge_lossy(file);
+        }
+        files.sections
+    }
+
+    /// Sections of each named test, for each source file. Sections without
+    /// test name are not listed.
+    pub fn list_tests(&self) -> BTreeMap<PathBuf, BTreeMap<String, SectionValue>> {
+        let mut tests = BTreeMap::<PathBuf, BTreeMap<String, SectionValue>>::new();
+        for (key, value) in self.report.sections.iter() {
+            if key.test_name.is_empty() {
+                continue;
+            }
+            tests
+                .entry(key.source_file.clone())
+                .or_default()
+                .insert(key.test_name.clone(), value.clone());
+        }
+        tests
+    }
+
+    fn read_report(path: &Path, fs: &impl FileSystem) -> Result<String, String> {
+        let content = match path == Path::new(STDIN_PATH) {
+            true => fs.read_stdin(),
+            false => fs.read(path),
+        }
+        .map_err(|e| e.to_string())?;
+        String::from_utf8(decompress(path, content)?).map_err(|e| e.to_string())
+    }
+
+    /// gcov writes one report per translation unit, so a directory of gcov JSON
+    /// reports is read as a single input
+    fn parse_gcov_json_directory(input: &Input, fs: &impl FileSystem) -> Result<Self, InputError> {
+        let files = fs
+            .list_files_recursively(&input.path)
+            .map_err(|e| InputError::read(input, input.path.clone(), e))?
+            .into_iter()
+            .filter(|file| gcov_json::is_gcov_json_file(file))
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return Err(InputError::discovery(
+                input,
+                format!(
+                    "No gcov JSON report found in directory {}",
+                    input.path.display()
+                ),
+            ));
+        }
+
+        let mut contents = vec![];
+        for file in files {
+            let content =
+                Self::read_report(&file, fs).map_err(|e| InputError::read(input, file, e))?;
+            contents.push(content);
+        }
+        gcov_json::parse_all(contents.iter().map(String::as_str))
+            .map(|(report, end_lines)| Self::new(report).with_function_end_lines(end_lines))
+            .map_err(|e| InputError::parse(input, None, e))
+    }
+
+    /// Parse a report in the given format. When the format is line based, the
+    /// line of the error is given along with it.
+    fn parse_report(content: &str, format: InputFormat) -> Result<Self, (Option<usize>, String)> {
+        let without_line = |e: String| (None, e);
+        match format {
+            InputFormat::Lcov => Self::parse_lcov_report(content),
+            InputFormat::Cobertura => cobertura::parse(content)
+                .map(Self::new)
+                .map_err(without_line),
+            InputFormat::JaCoCo => jacoco::parse(content).map(Self::new).map_err(without_line),
+            InputFormat::LlvmCov => llvm_cov::parse(content)
+                .map(|(report, regions)| Self::new(report).with_regions(regions))
+                .map_err(without_line),
+            InputFormat::GcovJson => gcov_json::parse(content)
+                .map(|(report, end_lines)| Self::new(report).with_function_end_lines(end_lines))
+                .map_err(without_line),
+            InputFormat::GoCover => go_cover::parse(content)
+                .map(Self::new)
+                .map_err(without_line),
+            InputFormat::Istanbul => istanbul::parse(content)
+                .map(Self::new)
+                .map_err(without_line),
+        }
+    }
+
+    /// Each line of a LCOV report is a record, so the line of an error is the
+    /// number of records read when it happens
+    fn parse_lcov_report(content: &str) -> Result<Self, (Option<usize>, String)> {
+        let records_count = Cell::new(0);
+        let mut source_file = PathBuf::new();
+        let mut function_end_lines = FunctionEndLines::new();
+        let records = Reader::new(content.as_bytes())
+            .inspect(|_| records_count.set(records_count.get() + 1))
+            .map(|record| match record {
+                Ok(Record::SourceFile { path }) => {
+                    source_file = path.clone();
+                    Ok(Record::SourceFile { path })
+                }
+                Ok(Record::FunctionName { name, start_line }) => {
+                    let name = match Self::split_function_end_line(&name) {
+                        Some((end_line, name)) => {
+                            function_end_lines
+                                .entry(source_file.clone())
+                                .or_default()
+                                .insert(name.to_string(), end_line);
+                            name.to_string()
+                        }
+                        None => name,
+                    };
+                    Ok(Record::FunctionName { name, start_line })
+                }
+                record => record,
+            });
+        let report = LcovReport::from_reader(records)
+            .map_err(|e| (Some(records_count.get()), Self::describe_lcov_error(e)))?;
+        Ok(Self::new(report).with_function_end_lines(function_end_lines))
+    }
+
+    /// Errors of invalid records already give their line, which is given
+    /// along with the error instead
+    fn describe_lcov_error(error: ParseError) -> String {
+        match error {
+            ParseError::Read(ReadError::ParseRecord(_, e)) => format!("invalid record: {}", e),
+            e => e.to_string(),
+        }
+    }
+
+    /// LCOV 2 writes `FN:<start>,<end>,<name>` records, the end line then
+    /// comes first in what the lcov crate reads as the name. Function names
+    /// can't start with a digit, so there is no ambiguity.
+    fn split_function_end_line(name: &str) -> Option<(u32, &str)> {
+        let (end_line, name) = name.split_once(',')?;
+        Some((end_line.parse().ok()?, name))
+    }
+
+    #[cfg(test)]
     pub fn list_sections(&self) -> BTreeMap<SectionKey, SectionValue> {
         self.report.sections.clone()
     }
 
-    pub fn with_prefix(self, prefix: &str) -> AggregatorInput {
+    pub fn with_prefix(self, prefix: &str) -> Result<AggregatorInput, String> {
         let prefix_parts = prefix
             .split('/')
             .filter(|part| !part.is_empty())
             .collect::<Vec<&str>>();
         for (key, _) in self.report.sections.iter() {
-            let source_file_parts = key
-                .source_file
-                .to_str()
-                .unwrap()
-                .split('/')
-                .collect::<Vec<&str>>();
+            let source_file = key.source_file.to_string_lossy();
+            let source_file_parts = source_file.split('/').collect::<Vec<&str>>();
 
             if !source_file_parts.starts_with(&prefix_parts) {
-                panic!(
+                return Err(format!(
                     "Some tested files do not start with the prefix '{}'. For example, {}",
                     prefix,
-                    key.source_file.to_str().unwrap()
-                );
+                    key.source_file.display()
+                ));
             }
         }
 
-        AggregatorInput {
-            report: self.report,
+        Ok(AggregatorInput {
             prefix: prefix.to_string(),
-            key: self.key,
-            name: self.name,
-        }
+            ..self
+        })
     }
 
     pub fn with_key(self, key: &str) -> AggregatorInput {
         AggregatorInput {
-            report: self.report,
-            prefix: self.prefix,
             key: key.to_string(),
-            name: self.name,
+            ..self
+        }
+    }
+
+    pub fn with_regions(self, regions: BTreeMap<PathBuf, AggregatedCoverageCounters>) -> Self {
+        AggregatorInput { regions, ..self }
+    }
+
+    pub fn get_regions(&self, source_file: &Path) -> Option<&AggregatedCoverageCounters> {
+        self.regions.get(source_file)
+    }
+
+    pub fn with_function_end_lines(self, function_end_lines: FunctionEndLines) -> Self {
+        AggregatorInput {
+            function_end_lines,
+            ..self
         }
     }
 
+    pub fn get_function_end_lines(&self, source_file: &Path) -> Option<&BTreeMap<String, u32>> {
+        self.function_end_lines.get(source_file)
+    }
+
     fn drop_file_name(source_file: &str) -> Vec<&str> {
         let parts = source_file.split('/').collect::<Vec<&str>>();
         let len = parts.len();
@@ -91,10 +457,16 @@ impl AggregatorInput {
     }
 
     fn find_longest_prefix(&self) -> String {
-        let sections = self.report.sections.clone();
-        let file_paths: Vec<Vec<&str>> = sections
+        // Paths that are not valid UTF-8 are compared on their lossy conversion
+        let source_files = self
+            .report
+            .sections
             .keys()
-            .map(|key| Self::drop_file_name(key.source_file.to_str().unwrap()))
+            .map(|key| key.source_file.to_string_lossy())
+            .collect::<Vec<_>>();
+        let file_paths: Vec<Vec<&str>> = source_files
+            .iter()
+            .map(|source_file| Self::drop_file_name(source_file))
             .collect();
 
         if file_paths.is_empty() {
@@ -148,12 +520,57 @@ impl AggregatorInput {
         self.prefix.split('/').last().unwrap_or("")
     }
 
-    pub fn build_from_inputs(inputs: Vec<Input>, fs: &impl FileSystem) -> Vec<AggregatorInput> {
+    /// Inputs of the same group are put together, in the order of their first
+    /// appearance. An input without group is alone in its own.
+    pub fn group_inputs(inputs: Vec<Input>) -> Vec<Vec<Input>> {
+        let mut groups = Vec::<Vec<Input>>::new();
+        for input in inputs {
+            let position = input.group.as_ref().and_then(|group| {
+                groups
+                    .iter()
+                    .position(|inputs| inputs[0].group.as_ref() == Some(group))
+            });
+            match position {
+                Some(position) => groups[position].push(input),
+                None => groups.push(vec![input]),
+            }
+        }
+        groups
+    }
+
+    /// Load each group of inputs into a single one, and give each of them a
+    /// unique key. When `keep_going` is set, inputs that fail to load are
+    /// skipped, and their errors are returned along with the loaded inputs.
+    pub fn build_from_inputs(
+        input_groups: Vec<Vec<Input>>,
+        fs: &impl FileSystem,
+        keep_going: bool,
+    ) -> Result<(Vec<AggregatorInput>, Vec<InputError>), InputError> {
         let mut report_names = HashMap::<String, u32>::new();
         let mut report_inputs = Vec::<AggregatorInput>::new();
+        let mut skipped_inputs = Vec::<InputError>::new();
 
-        for config_input in inputs.into_iter() {
-            let aggregator_input = AggregatorInput::from_config_input(config_input, fs);
+        for config_inputs in input_groups.into_iter() {
+            let mut loaded_inputs = vec![];
+            for config_input in config_inputs.iter() {
+                match Self::load(config_input, fs) {
+                    Ok(loaded_input) => loaded_inputs.push(loaded_input),
+                    Err(e) if keep_going => skipped_inputs.push(e),
+                    Err(e) => return Err(e),
+                }
+            }
+            if loaded_inputs.is_empty() {
+                continue;
+            }
+
+            let aggregator_input = match Self::from_loaded_inputs(&config_inputs, loaded_inputs) {
+                Ok(aggregator_input) => aggregator_input,
+                Err(e) if keep_going => {
+                    skipped_inputs.push(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             let wanted_key = aggregator_input.last_part_of_prefix().to_string();
             report_names
                 .entry(wanted_key)
@@ -179,16 +596,27 @@ impl AggregatorInput {
             inputs_with_key.push(input.with_key(&key));
         }
 
-        inputs_with_key
+        Ok((inputs_with_key, skipped_inputs))
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{core::MockFileSystem, test_utils::builders::InsertSection};
+    use crate::{
+        core::MockFileSystem,
+        test_utils::builders::{InsertBranch, InsertFunction, InsertLine, InsertSection},
+    };
+    use lcov::report::section::Value as SectionValue;
     use lcov::Report;
 
+    fn load_group(inputs: Vec<Input>, fs: &MockFileSystem) -> AggregatorInput {
+        AggregatorInput::build_from_inputs(vec![inputs], fs, false)
+            .unwrap()
+            .0
+            .remove(0)
+    }
+
     #[test]
     fn test_new_list_sections() {
         let report = Report::new();
@@ -200,18 +628,50 @@ mod test {
     fn test_with_prefix_list_sections() {
         let report = Report::new().insert_empty_section("my/very/long/path/file.cpp");
         let input = AggregatorInput::new(report.clone());
-        let new_input = input.with_prefix("my/very/long/path/");
+        let new_input = input.with_prefix("my/very/long/path/").unwrap();
         assert_eq!(new_input.prefix, "my/very/long/path/");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn when_a_tested_file_path_is_not_valid_utf8_it_shall_be_loaded_lossily() {
+        use crate::{
+            aggregation::tested_root::{BuildContext, TestedRoot},
+            core::{TestedContainer, TestedFile},
+        };
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let mut report = Report::new();
+        report.sections.insert(
+            SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from(OsStr::from_bytes(b"project/src/caf\xe9.rs")),
+            },
+            SectionValue::default(),
+        );
+        assert!(AggregatorInput::new(report.clone())
+            .with_prefix("project")
+            .is_ok());
+        let input = AggregatorInput::new(report).with_longest_prefix();
+        assert_eq!(input.get_prefix(), "project/src");
+
+        let root = TestedRoot::new(input, &BuildContext::default());
+        assert_eq!(
+            root.get_code_file_children()
+                .map(|file| file.get_original_file_path())
+                .collect::<Vec<_>>(),
+            vec![PathBuf::from("project/src/caf\u{FFFD}.rs")]
+        );
+    }
+
     #[test]
-    #[should_panic(
-        expected = "Some tested files do not start with the prefix 'my/very/long/path/'. For example, another/prefix/file.cpp"
-    )]
     fn test_with_prefix_list_sections_with_invalid_prefix() {
         let report = Report::new().insert_empty_section("another/prefix/file.cpp");
         let input = AggregatorInput::new(report.clone());
-        input.with_prefix("my/very/long/path/");
+        assert_eq!(
+            input.with_prefix("my/very/long/path/").unwrap_err(),
+            "Some tested files do not start with the prefix 'my/very/long/path/'. For example, another/prefix/file.cpp"
+        );
     }
 
     #[test]
@@ -266,34 +726,44 @@ mod test {
     #[test]
     fn last_part_of_prefix_with_single_part_is_single_part() {
         let report = Report::new();
-        let input = AggregatorInput::new(report.clone()).with_prefix("foo");
+        let input = AggregatorInput::new(report.clone())
+            .with_prefix("foo")
+            .unwrap();
         assert_eq!(input.last_part_of_prefix(), "foo");
     }
 
     #[test]
     fn last_part_of_absolute_prefix_with_single_part_is_single_part() {
         let report = Report::new();
-        let input = AggregatorInput::new(report.clone()).with_prefix("/foo");
+        let input = AggregatorInput::new(report.clone())
+            .with_prefix("/foo")
+            .unwrap();
         assert_eq!(input.last_part_of_prefix(), "foo");
     }
 
     #[test]
     fn last_part_of_absolute_prefix_with_multiple_part_is_last_part() {
         let report = Report::new();
-        let input = AggregatorInput::new(report.clone()).with_prefix("/foo/bar");
+        let input = AggregatorInput::new(report.clone())
+            .with_prefix("/foo/bar")
+            .unwrap();
         assert_eq!(input.last_part_of_prefix(), "bar");
     }
 
     #[test]
     fn when_creating_aggregator_inputs_from_one_config_input_then_key_is_last_part_of_prefix() {
         let mut fs = MockFileSystem::new();
-        fs.expect_read_to_string().returning(|_| Ok("".to_string()));
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|_| Ok(vec![]));
         let input = vec![Input::from_name_prefix_and_path(
             "Lib".into(),
             "project/package_1/src".into(),
             "./report.info".into(),
         )];
-        let aggregator_input = AggregatorInput::build_from_inputs(input, &fs);
+        let aggregator_input =
+            AggregatorInput::build_from_inputs(AggregatorInput::group_inputs(input), &fs, false)
+                .unwrap()
+                .0;
 
         assert_eq!(aggregator_input.len(), 1);
         assert_eq!(aggregator_input[0].get_key(), "src");
@@ -302,7 +772,8 @@ mod test {
     #[test]
     fn when_creating_aggregator_inputs_from_two_config_input_with_same_key_then_key_deduplicated() {
         let mut fs = MockFileSystem::new();
-        fs.expect_read_to_string().returning(|_| Ok("".to_string()));
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|_| Ok(vec![]));
         let input = vec![
             Input::from_name_prefix_and_path(
                 "Lib".into(),
@@ -315,10 +786,408 @@ mod test {
                 "./report.info".into(),
             ),
         ];
-        let aggregator_input = AggregatorInput::build_from_inputs(input, &fs);
+        let aggregator_input =
+            AggregatorInput::build_from_inputs(AggregatorInput::group_inputs(input), &fs, false)
+                .unwrap()
+                .0;
 
         assert_eq!(aggregator_input.len(), 2);
         assert_eq!(aggregator_input[0].get_key(), "src_1");
         assert_eq!(aggregator_input[1].get_key(), "src_2");
     }
+
+    #[test]
+    fn when_creating_aggregator_input_from_a_cobertura_report_it_shall_detect_the_format() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|_| {
+            Ok(r#"<?xml version="1.0" ?>
+<coverage><sources><source>/project</source></sources><packages><package><classes>
+<class filename="src/main.py"><lines><line number="1" hits="1"/></lines></class>
+</classes></package></packages></coverage>"#
+                .into())
+        });
+        let aggregator_input = load_group(vec![Input::from_path("coverage.xml".into())], &fs);
+
+        let sections = aggregator_input.list_sections();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(
+            sections.keys().next().unwrap().source_file,
+            std::path::PathBuf::from("/project/src/main.py")
+        );
+        assert_eq!(aggregator_input.get_prefix(), "/project/src");
+    }
+
+    #[test]
+    fn when_creating_aggregator_input_from_a_directory_gcov_json_reports_shall_be_merged() {
+        fn compress(content: &str) -> Vec<u8> {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            std::io::Write::write_all(&mut encoder, content.as_bytes()).unwrap();
+            encoder.finish().unwrap()
+        }
+
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| true);
+        fs.expect_list_files_recursively().returning(|_| {
+            Ok(vec![
+                "build/a.gcov.json.gz".into(),
+                "build/b.gcov.json.gz".into(),
+                "build/a.gcda".into(),
+            ])
+        });
+        fs.expect_read().returning(|path| {
+            let (file, count) = match path.to_str().unwrap() {
+                "build/a.gcov.json.gz" => ("/project/src/a.cpp", 1),
+                "build/b.gcov.json.gz" => ("/project/src/b.cpp", 2),
+                _ => panic!("Only gcov JSON reports shall be read"),
+            };
+            Ok(compress(&format!(
+                r#"{{"gcc_version": "13.2.0", "files": [
+                    {{"file": "{file}", "functions": [], "lines": [{{"line_number": 1, "count": {count}, "branches": []}}]}},
+                    {{"file": "/project/include/a.hpp", "functions": [], "lines": [{{"line_number": 1, "count": {count}, "branches": []}}]}}
+                ]}}"#
+            )))
+        });
+        let aggregator_input = load_group(vec![Input::from_path("build".into())], &fs);
+
+        let sections = aggregator_input.list_sections();
+        assert_eq!(sections.len(), 3);
+        let header = sections
+            .iter()
+            .find(|(key, _)| key.source_file == Path::new("/project/include/a.hpp"))
+            .unwrap()
+            .1;
+        assert_eq!(header.lines.values().next().unwrap().count, 3);
+        assert_eq!(aggregator_input.get_prefix(), "/project");
+    }
+
+    #[test]
+    fn when_creating_aggregator_input_from_stdin_it_shall_read_and_decompress_stdin() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().never();
+        fs.expect_read_stdin().returning(|| {
+            Ok(zstd::stream::encode_all(
+                "SF:/project/src/main.cpp\nDA:1,1\nend_of_record\n".as_bytes(),
+                0,
+            )
+            .unwrap())
+        });
+        let aggregator_input = load_group(vec![Input::from_path(STDIN_PATH.into())], &fs);
+
+        let sections = aggregator_input.list_sections();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(aggregator_input.get_prefix(), "/project/src");
+    }
+
+    #[test]
+    fn when_merging_inputs_hits_of_the_same_file_shall_be_summed() {
+        let unit = Report::new().insert_section(
+            "/project/src/lib.rs",
+            SectionValue::default()
+                .insert_line(1, 1)
+                .insert_line(2, 0)
+                .insert_function("add", 1)
+                .insert_branch(1, 0),
+        );
+        let e2e = Report::new()
+            .insert_section(
+                "/project/src/lib.rs",
+                SectionValue::default()
+                    .insert_line(2, 3)
+                    .insert_function("add", 2)
+                    .insert_branch(1, 2),
+            )
+            .insert_section(
+                "/project/src/main.rs",
+                SectionValue::default().insert_line(1, 1),
+            );
+
+        let merged = AggregatorInput::new(unit).merge(AggregatorInput::new(e2e));
+
+        let expected = Report::new()
+            .insert_section(
+                "/project/src/lib.rs",
+                SectionValue::default()
+                    .insert_line(1, 1)
+                    .insert_line(2, 3)
+                    .insert_function("add", 3)
+                    .insert_branch(1, 2),
+            )
+            .insert_section(
+                "/project/src/main.rs",
+                SectionValue::default().insert_line(1, 1),
+            );
+        assert_eq!(merged.list_sections(), expected.sections);
+    }
+
+    #[test]
+    fn when_listing_files_sections_of_different_tests_shall_be_merged() {
+        let mut report = Report::new();
+        for (test_name, count) in [("unit", 1), ("e2e", 2)] {
+            report.sections.insert(
+                SectionKey {
+                    test_name: test_name.to_string(),
+                    source_file: PathBuf::from("/project/src/lib.rs"),
+                },
+                SectionValue::default().insert_line(1, count),
+            );
+        }
+
+        let files = AggregatorInput::new(report).list_files();
+
+        let expected = Report::new().insert_section(
+            "/project/src/lib.rs",
+            SectionValue::default().insert_line(1, 3),
+        );
+        assert_eq!(files, expected.sections);
+    }
+
+    #[test]
+    fn when_writing_an_input_as_lcov_it_shall_be_read_back_identically() {
+        let report = Report::new().insert_section(
+            "/project/src/lib.rs",
+            SectionValue::default()
+                .insert_line(1, 1)
+                .insert_line(2, 0)
+                .insert_branch(1, 2),
+        );
+        let lcov = AggregatorInput::new(report.clone()).to_lcov_string();
+
+        let read_back = LcovReport::from_reader(Reader::new(lcov.as_bytes())).unwrap();
+        assert_eq!(read_back.sections, report.sections);
+    }
+
+    #[test]
+    fn when_grouping_inputs_inputs_of_the_same_group_shall_be_together() {
+        let input = |path: &str, group: Option<&str>| Input {
+            path: path.into(),
+            group: group.map(String::from),
+            ..Default::default()
+        };
+        let groups = AggregatorInput::group_inputs(vec![
+            input("unit.info", Some("Backend")),
+            input("front.info", None),
+            input("e2e.info", Some("Backend")),
+            input("other.info", None),
+        ]);
+
+        let paths = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|input| input.path.to_str().unwrap())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                vec!["unit.info", "e2e.info"],
+                vec!["front.info"],
+                vec!["other.info"]
+            ]
+        );
+    }
+
+    #[test]
+    fn when_creating_aggregator_input_from_a_group_it_shall_be_named_after_the_group() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|path| {
+            Ok(format!(
+                "SF:/project/{}/lib.rs\nDA:1,1\nend_of_record\n",
+                path.display()
+            )
+            .into())
+        });
+        let inputs = ["unit", "e2e"]
+            .into_iter()
+            .map(|path| Input {
+                path: path.into(),
+                group: Some("Backend".to_string()),
+                ..Default::default()
+            })
+            .collect();
+
+        let aggregator_input = load_group(inputs, &fs);
+
+        assert_eq!(aggregator_input.get_name(), "Backend");
+        assert_eq!(aggregator_input.get_prefix(), "/project");
+        assert_eq!(aggregator_input.list_sections().len(), 2);
+    }
+
+    #[test]
+    fn when_an_lcov_report_is_invalid_it_shall_fail_with_the_line_of_the_error() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read()
+            .returning(|_| Ok("SF:/project/lib.rs\nDA:1,1\nDA:x,1\nend_of_record\n".into()));
+        let input = Input::from_name_and_path("Lib".into(), "lcov.info".into());
+
+        let error = AggregatorInput::build_from_inputs(vec![vec![input]], &fs, false).unwrap_err();
+
+        assert!(matches!(error, InputError::Parse { line: Some(3), .. }));
+        assert_eq!(
+            error.to_string(),
+            "Input 'Lib': invalid report lcov.info at line 3: invalid record: invalid value of field `line`: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn when_an_lcov_report_gives_function_end_lines_they_shall_be_split_from_names() {
+        let report = concat!(
+            "SF:/project/lib.rs\n",
+            "FN:3,7,add\n",
+            "FN:9,sub\n",
+            "FNDA:1,add\n",
+            "FNDA:0,sub\n",
+            "end_of_record\n"
+        );
+
+        let input = AggregatorInput::parse_lcov_report(report).unwrap();
+
+        let functions = input
+            .report
+            .sections
+            .values()
+            .next()
+            .unwrap()
+            .functions
+            .iter();
+        assert_eq!(
+            functions
+                .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+                .collect::<Vec<_>>(),
+            vec![("add", Some(3), 1), ("sub", Some(9), 0)]
+        );
+        assert_eq!(
+            input.get_function_end_lines(Path::new("/project/lib.rs")),
+            Some(&BTreeMap::from([("add".to_string(), 7)]))
+        );
+    }
+
+    #[test]
+    fn when_keeping_going_inputs_that_fail_to_load_shall_be_skipped() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read()
+            .returning(|path| match path.to_str().unwrap() {
+                "missing.info" => Err("No such file or directory".into()),
+                _ => Ok("SF:/project/src/lib.rs\nDA:1,1\nend_of_record\n".into()),
+            });
+        let inputs = vec![
+            vec![Input::from_path("missing.info".into())],
+            vec![Input::from_path("lcov.info".into())],
+        ];
+
+        let (inputs, skipped) = AggregatorInput::build_from_inputs(inputs, &fs, true).unwrap();
+
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].get_prefix(), "/project/src");
+        assert_eq!(
+            skipped,
+            vec![InputError::Read {
+                input: "missing.info".to_string(),
+                path: "missing.info".into(),
+                cause: "No such file or directory".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn when_loading_an_input_with_path_mappings_only_display_ones_shall_rename_files() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|_| {
+            Ok("SF:/workspace/src/lib.rs\nDA:1,1\nend_of_record\nSF:/build/api.rs\nDA:1,1\nend_of_record\n".into())
+        });
+        let input = Input {
+            path: "lcov.info".into(),
+            path_mappings: vec![
+                PathMapping::new("/build".into(), "/workspace/gen".into(), true),
+                PathMapping::new("/workspace".into(), "/home/me/project".into(), false),
+            ],
+            ..Default::default()
+        };
+
+        let aggregator_input = load_group(vec![input], &fs);
+
+        assert_eq!(aggregator_input.get_prefix(), "/workspace");
+        assert_eq!(
+            aggregator_input.get_source_path(Path::new("/workspace/gen/api.rs")),
+            PathBuf::from("/home/me/project/gen/api.rs")
+        );
+        assert_eq!(
+            aggregator_input.get_source_path(Path::new("/elsewhere/lib.rs")),
+            PathBuf::from("/elsewhere/lib.rs")
+        );
+    }
+
+    #[test]
+    fn when_merging_inputs_with_source_roots_they_shall_be_kept_once_in_order() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read()
+            .returning(|_| Ok("SF:src/lib.rs\nDA:1,1\nend_of_record\n".into()));
+        let inputs = vec![
+            Input {
+                path: "unit.info".into(),
+                source_roots: vec!["/home/me/project".into(), "/shared".into()],
+                ..Default::default()
+            },
+            Input {
+                path: "e2e.info".into(),
+                source_roots: vec!["/home/me/e2e".into(), "/shared".into()],
+                ..Default::default()
+            },
+        ];
+
+        let aggregator_input = load_group(inputs, &fs);
+
+        assert_eq!(
+            aggregator_input.get_source_roots(),
+            &[
+                PathBuf::from("/home/me/project"),
+                PathBuf::from("/shared"),
+                PathBuf::from("/home/me/e2e")
+            ]
+        );
+    }
+
+    #[test]
+    fn when_loading_an_input_with_filters_removed_files_shall_be_counted_once_per_filter() {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir().returning(|_| false);
+        fs.expect_read().returning(|_| {
+            Ok("TN:unit\nSF:/project/src/lib.rs\nDA:1,1\nend_of_record\nSF:/project/vendor/dep.rs\nDA:1,1\nend_of_record\n\
+TN:e2e\nSF:/project/vendor/dep.rs\nDA:1,1\nend_of_record\nSF:/project/api.pb.rs\nDA:1,1\nend_of_record\n"
+                .into())
+        });
+        let input = Input {
+            path: "lcov.info".into(),
+            exclude: vec!["**/vendor/**".to_string(), "*.pb.rs".to_string()],
+            ..Default::default()
+        };
+
+        let aggregator_input = load_group(vec![input], &fs);
+
+        assert_eq!(
+            aggregator_input
+                .list_files()
+                .keys()
+                .map(|key| key.source_file.clone())
+                .collect::<Vec<_>>(),
+            vec![PathBuf::from("/project/src/lib.rs")]
+        );
+        assert_eq!(
+            aggregator_input.get_removed_files(),
+            &RemovedFiles::from([
+                ("exclude '**/vendor/**'".to_string(), 1),
+                ("exclude '*.pb.rs'".to_string(), 1),
+            ])
+        );
+    }
 }
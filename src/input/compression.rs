@@ -0,0 +1,112 @@
+use std::{io::Read, path::Path};
+
+use flate2::read::GzDecoder;
+
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC_BYTES: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+impl Compression {
+    /// The extension of the file is trusted first. When it is not one of a
+    /// compressed file (or there is no file name at all, like for stdin),
+    /// the first bytes of the content are looked at.
+    pub fn detect(path: &Path, content: &[u8]) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") | Some("gzip") => return Compression::Gzip,
+            Some("zst") | Some("zstd") => return Compression::Zstd,
+            _ => {}
+        }
+
+        if content.starts_with(&GZIP_MAGIC_BYTES) {
+            Compression::Gzip
+        } else if content.starts_with(&ZSTD_MAGIC_BYTES) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+pub fn decompress(path: &Path, content: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut decompressed = Vec::new();
+    match Compression::detect(path, &content) {
+        Compression::None => return Ok(content),
+        Compression::Gzip => GzDecoder::new(content.as_slice())
+            .read_to_end(&mut decompressed)
+            .map_err(|e| format!("Invalid gzip file: {}", e))?,
+        Compression::Zstd => zstd::stream::read::Decoder::new(content.as_slice())
+            .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+            .map_err(|e| format!("Invalid zstd file: {}", e))?,
+    };
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    const REPORT: &str = "SF:main.cpp\nDA:1,1\nend_of_record\n";
+
+    fn gzip(content: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zstd(content: &str) -> Vec<u8> {
+        zstd::stream::encode_all(content.as_bytes(), 0).unwrap()
+    }
+
+    #[test]
+    fn when_extension_is_a_compression_one_it_shall_decide_the_compression() {
+        assert_eq!(
+            Compression::detect(Path::new("lcov.info.gz"), b""),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::detect(Path::new("lcov.info.zst"), b""),
+            Compression::Zstd
+        );
+    }
+
+    #[test]
+    fn when_extension_is_unknown_magic_bytes_shall_decide_the_compression() {
+        assert_eq!(
+            Compression::detect(Path::new("-"), &gzip(REPORT)),
+            Compression::Gzip
+        );
+        assert_eq!(
+            Compression::detect(Path::new("lcov.info"), &zstd(REPORT)),
+            Compression::Zstd
+        );
+        assert_eq!(
+            Compression::detect(Path::new("lcov.info"), REPORT.as_bytes()),
+            Compression::None
+        );
+    }
+
+    #[test]
+    fn when_decompressing_it_shall_return_the_original_content() {
+        for content in [gzip(REPORT), zstd(REPORT), REPORT.as_bytes().to_vec()] {
+            assert_eq!(
+                decompress(Path::new("-"), content).unwrap(),
+                REPORT.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn when_extension_does_not_match_content_it_shall_fail() {
+        assert!(
+            decompress(Path::new("lcov.info.gz"), REPORT.as_bytes().to_vec())
+                .unwrap_err()
+                .starts_with("Invalid gzip file: ")
+        );
+    }
+}
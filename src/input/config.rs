@@ -1,11 +1,141 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+use super::formats::{gcov_json, go_cover, istanbul, llvm_cov, xml::get_root_element_name};
+use crate::aggregation::thresholds::Thresholds;
+
+/// Path of an input to read the report from the standard input
+pub const STDIN_PATH: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum InputFormat {
+    #[serde(rename = "lcov")]
+    Lcov,
+
+    #[serde(rename = "cobertura")]
+    Cobertura,
+
+    #[serde(rename = "jacoco")]
+    JaCoCo,
+
+    #[serde(rename = "llvm-cov")]
+    LlvmCov,
+
+    #[serde(rename = "gcov-json")]
+    GcovJson,
+
+    #[serde(rename = "go")]
+    GoCover,
+
+    #[serde(rename = "istanbul")]
+    Istanbul,
+}
+impl InputFormat {
+    /// Guess the format of a coverage report from its content. Anything that
+    /// is not recognized as another format is considered to be LCOV
+    pub fn detect(content: &str) -> Self {
+        let content = content.trim_start_matches('\u{feff}').trim_start();
+        if content.starts_with('{') && content.contains(llvm_cov::EXPORT_TYPE) {
+            return InputFormat::LlvmCov;
+        }
+        if content.starts_with('{') && content.contains(gcov_json::GCC_VERSION_KEY) {
+            return InputFormat::GcovJson;
+        }
+        if content.starts_with('{') && content.contains(istanbul::STATEMENT_MAP_KEY) {
+            return InputFormat::Istanbul;
+        }
+        if content.starts_with(go_cover::MODE_PREFIX) {
+            return InputFormat::GoCover;
+        }
+        if !content.starts_with('<') {
+            return InputFormat::Lcov;
+        }
+        match get_root_element_name(content) {
+            Some("coverage") => InputFormat::Cobertura,
+            Some("report") => InputFormat::JaCoCo,
+            _ => InputFormat::Lcov,
+        }
+    }
+}
+
+/// Rewrite source paths starting with `from` to start with `to` instead, for
+/// reports generated on another machine (or container) than the sources.
+/// Paths are only rewritten to find the sources, unless `display` is set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PathMapping {
+    pub from: PathBuf,
+    pub to: PathBuf,
+
+    #[serde(default)]
+    pub display: bool,
+}
+impl PathMapping {
+    pub fn new(from: PathBuf, to: PathBuf, display: bool) -> Self {
+        Self { from, to, display }
+    }
+
+    /// Rewrite the path with the first mapping that applies to it, if any
+    pub fn map_path<'a>(
+        mappings: impl IntoIterator<Item = &'a PathMapping>,
+        path: &Path,
+    ) -> Option<PathBuf> {
+        mappings.into_iter().find_map(|mapping| {
+            path.strip_prefix(&mapping.from)
+                .ok()
+                .map(|relative| mapping.to.join(relative))
+        })
+    }
+}
+
+/// Minimal coverage of the modules and files whose path in the report matches
+/// a glob pattern. Only the first rule matching a module or a file applies to
+/// it, so that a rule without any threshold exempts it from the next ones.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdRule {
+    pub path: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub functions: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branches: Option<f32>,
+}
+impl ThresholdRule {
+    pub fn get_thresholds(&self) -> Thresholds {
+        Thresholds {
+            lines: self.lines,
+            functions: self.functions,
+            branches: self.branches,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Input {
     pub name: Option<String>,
     pub prefix: Option<PathBuf>,
     pub path: PathBuf,
+    pub format: Option<InputFormat>,
+    pub group: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_mappings: Vec<PathMapping>,
+
+    /// Glob patterns of the tested files to keep, all of them if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Glob patterns of the tested files to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// Directories where the sources with a relative path are looked for, in
+    /// order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_roots: Vec<PathBuf>,
 }
 impl Input {
     pub fn from_path(path: PathBuf) -> Self {
@@ -23,11 +153,55 @@ impl Input {
         }
     }
 
+    /// Name of the input to show in messages: its name, or group, if any, or
+    /// else its path
+    pub fn get_display_name(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| self.group.clone())
+            .unwrap_or_else(|| self.path.display().to_string())
+    }
+
     pub fn from_name_prefix_and_path(name: String, prefix: PathBuf, path: PathBuf) -> Self {
         Self {
             name: Some(name),
             prefix: Some(prefix),
             path,
+            ..Default::default()
+        }
+    }
+}
+
+/// Markers in source comments excluding lines from the coverage, like genhtml
+/// does. Lines between a start and a stop marker are excluded too. Branch
+/// markers only exclude the branches of the lines, not the lines themselves.
+/// Sources are only read to look for them when enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExclusionMarkers {
+    pub enabled: bool,
+    pub line: String,
+    pub start: String,
+    pub stop: String,
+    pub branch_line: String,
+    pub branch_start: String,
+    pub branch_stop: String,
+}
+impl ExclusionMarkers {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+impl Default for ExclusionMarkers {
+    fn default() -> Self {
+        ExclusionMarkers {
+            enabled: true,
+            line: "LCOV_EXCL_LINE".to_string(),
+            start: "LCOV_EXCL_START".to_string(),
+            stop: "LCOV_EXCL_STOP".to_string(),
+            branch_line: "LCOV_EXCL_BR_LINE".to_string(),
+            branch_start: "LCOV_EXCL_BR_START".to_string(),
+            branch_stop: "LCOV_EXCL_BR_STOP".to_string(),
         }
     }
 }
@@ -76,6 +250,98 @@ pub struct Config {
 
     #[serde(default)]
     pub reporter: Reporter,
+
+    #[serde(default)]
+    pub keep_going: bool,
+
+    /// Path mappings applying to all inputs, after their own ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_mappings: Vec<PathMapping>,
+
+    /// File filters applying to all inputs, along with their own ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "ExclusionMarkers::is_default")]
+    pub exclusion_markers: ExclusionMarkers,
+
+    /// Source roots of all inputs, tried after their own ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_roots: Vec<PathBuf>,
+
+    /// Git revision of the local repository to read the sources from, instead
+    /// of the working tree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_rev: Option<String>,
+
+    /// Unified diff whose changed lines shall be covered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<PathBuf>,
+
+    /// Git revision or range (`A..B`, `A...B`) whose changed lines shall be
+    /// covered, instead of a diff file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_base: Option<String>,
+
+    /// Report to compare the coverage with, loaded with the settings of the
+    /// inputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<PathBuf>,
+
+    /// Minimal percentages of covered lines, functions and branches of the
+    /// whole report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_under_lines: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_under_functions: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fail_under_branches: Option<f32>,
+
+    /// Minimal coverage of the modules and files of the report
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thresholds: Vec<ThresholdRule>,
+}
+impl Config {
+    pub fn get_thresholds(&self) -> Thresholds {
+        Thresholds {
+            lines: self.fail_under_lines,
+            functions: self.fail_under_functions,
+            branches: self.fail_under_branches,
+        }
+    }
+
+    /// Markers to look for in the sources, unless they are disabled
+    pub fn get_exclusion_markers(&self) -> Option<&ExclusionMarkers> {
+        Some(&self.exclusion_markers).filter(|markers| markers.enabled)
+    }
+
+    /// Give the path mappings, file filters and source roots of the
+    /// configuration to each of its inputs
+    pub fn with_inputs_settings(mut self) -> Self {
+        let inputs = std::mem::take(&mut self.inputs);
+        self.inputs = inputs
+            .into_iter()
+            .map(|input| self.with_input_settings(input))
+            .collect();
+        self
+    }
+
+    /// Give the path mappings, file filters and source roots of the
+    /// configuration to an input, after its own ones
+    pub fn with_input_settings(&self, mut input: Input) -> Input {
+        input
+            .path_mappings
+            .extend(self.path_mappings.iter().cloned());
+        input.include.extend(self.include.iter().cloned());
+        input.exclude.extend(self.exclude.iter().cloned());
+        input.source_roots.extend(self.source_roots.iter().cloned());
+        input
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +377,7 @@ path = "test3"
                         name: Some("test1".to_string()),
                         prefix: Some(PathBuf::from("test1")),
                         path: PathBuf::from("test1"),
+                        ..Default::default()
                     },
                     Input {
                         name: Some("test2".to_string()),
@@ -124,6 +391,7 @@ path = "test3"
                 ],
                 output: PathBuf::from("test"),
                 reporter: Reporter::MpaHtmlLightReporter,
+                ..Default::default()
             }
         );
     }
@@ -150,9 +418,11 @@ path = "test1"
                     name: Some("test1".to_string()),
                     prefix: Some(PathBuf::from("test1")),
                     path: PathBuf::from("test1"),
+                    ..Default::default()
                 }],
                 output: PathBuf::from("test"),
                 reporter: Reporter::TextSummaryReporter,
+                ..Default::default()
             }
         );
     }
@@ -179,10 +449,268 @@ path = "test1"
                     name: Some("test1".to_string()),
                     prefix: Some(PathBuf::from("test1")),
                     path: PathBuf::from("test1"),
+                    ..Default::default()
                 }],
                 output: PathBuf::from("test"),
                 reporter: Reporter::MpaHtmlLightReporter,
+                ..Default::default()
             }
         );
     }
+
+    #[test]
+    fn test_read_config_from_toml_with_input_format() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+
+[[inputs]]
+path = "coverage.xml"
+format = "cobertura"
+"#,
+        );
+
+        assert_eq!(
+            config.unwrap().inputs,
+            vec![Input {
+                path: PathBuf::from("coverage.xml"),
+                format: Some(InputFormat::Cobertura),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn when_content_is_an_lcov_report_detected_format_shall_be_lcov() {
+        assert_eq!(
+            InputFormat::detect("TN:\nSF:main.cpp\nend_of_record\n"),
+            InputFormat::Lcov
+        );
+    }
+
+    #[test]
+    fn when_content_is_a_cobertura_report_detected_format_shall_be_cobertura() {
+        assert_eq!(
+            InputFormat::detect(
+                r#"<?xml version="1.0" ?>
+<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+<coverage line-rate="0.5" branch-rate="0" version="1.9"></coverage>"#
+            ),
+            InputFormat::Cobertura
+        );
+    }
+
+    #[test]
+    fn when_content_is_a_jacoco_report_detected_format_shall_be_jacoco() {
+        assert_eq!(
+            InputFormat::detect(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><!DOCTYPE report PUBLIC "-//JACOCO//DTD Report 1.1//EN" "report.dtd"><report name="app"></report>"#
+            ),
+            InputFormat::JaCoCo
+        );
+    }
+
+    #[test]
+    fn when_content_is_an_llvm_cov_export_detected_format_shall_be_llvm_cov() {
+        assert_eq!(
+            InputFormat::detect(
+                r#"{"data":[],"type":"llvm.coverage.json.export","version":"2.0.1"}"#
+            ),
+            InputFormat::LlvmCov
+        );
+    }
+
+    #[test]
+    fn when_content_is_a_gcov_json_report_detected_format_shall_be_gcov_json() {
+        assert_eq!(
+            InputFormat::detect(r#"{"format_version":"1","gcc_version":"13.2.0","files":[]}"#),
+            InputFormat::GcovJson
+        );
+    }
+
+    #[test]
+    fn when_content_is_a_go_coverage_profile_detected_format_shall_be_go() {
+        assert_eq!(
+            InputFormat::detect("mode: set\nexample.com/pkg/file.go:1.1,2.2 1 1\n"),
+            InputFormat::GoCover
+        );
+    }
+
+    #[test]
+    fn when_content_is_an_istanbul_report_detected_format_shall_be_istanbul() {
+        assert_eq!(
+            InputFormat::detect(r#"{"/a.js": {"path": "/a.js", "statementMap": {}, "s": {}}}"#),
+            InputFormat::Istanbul
+        );
+    }
+
+    #[test]
+    fn when_reading_path_mappings_from_toml_display_shall_default_to_false() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+
+[[path_mappings]]
+from = "/workspace"
+to = "/home/me/project"
+
+[[inputs]]
+path = "test1"
+
+[[inputs.path_mappings]]
+from = "/build"
+to = "/home/me/build"
+display = true
+"#,
+        )
+        .unwrap()
+        .with_inputs_settings();
+
+        assert_eq!(
+            config.inputs[0].path_mappings,
+            vec![
+                PathMapping::new("/build".into(), "/home/me/build".into(), true),
+                PathMapping::new("/workspace".into(), "/home/me/project".into(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_reading_source_roots_from_toml_the_ones_of_inputs_shall_come_first() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+source_roots = ["/home/me/project"]
+
+[[inputs]]
+path = "test1"
+source_roots = ["/home/me/project/crates/lib"]
+"#,
+        )
+        .unwrap()
+        .with_inputs_settings();
+
+        assert_eq!(
+            config.inputs[0].source_roots,
+            vec![
+                PathBuf::from("/home/me/project/crates/lib"),
+                PathBuf::from("/home/me/project"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_reading_thresholds_from_toml_integers_shall_be_percentages_too() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+inputs = []
+fail_under_lines = 80
+fail_under_branches = 62.5
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.get_thresholds(),
+            Thresholds {
+                lines: Some(80.),
+                functions: None,
+                branches: Some(62.5),
+            }
+        );
+    }
+
+    #[test]
+    fn when_reading_threshold_rules_from_toml_they_shall_keep_their_order() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+inputs = []
+
+[[thresholds]]
+path = "experimental/**"
+
+[[thresholds]]
+path = "core"
+lines = 90
+branches = 75.5
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.thresholds,
+            vec![
+                ThresholdRule {
+                    path: "experimental/**".to_string(),
+                    ..Default::default()
+                },
+                ThresholdRule {
+                    path: "core".to_string(),
+                    lines: Some(90.),
+                    functions: None,
+                    branches: Some(75.5),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn when_mapping_a_path_the_first_matching_mapping_shall_apply() {
+        let mappings = vec![
+            PathMapping::new("/workspace/lib".into(), "/lib".into(), false),
+            PathMapping::new("/workspace".into(), "/home/me/project".into(), false),
+        ];
+        assert_eq!(
+            PathMapping::map_path(&mappings, Path::new("/workspace/src/main.rs")),
+            Some(PathBuf::from("/home/me/project/src/main.rs"))
+        );
+        assert_eq!(
+            PathMapping::map_path(&mappings, Path::new("/workspace/lib/lib.rs")),
+            Some(PathBuf::from("/lib/lib.rs"))
+        );
+        assert_eq!(
+            PathMapping::map_path(&mappings, Path::new("/workspaces/main.rs")),
+            None
+        );
+    }
+
+    #[test]
+    fn when_reading_exclusion_markers_from_toml_missing_ones_shall_keep_their_default() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+inputs = []
+
+[exclusion_markers]
+line = "NO_COVERAGE"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.exclusion_markers,
+            ExclusionMarkers {
+                line: "NO_COVERAGE".to_string(),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn when_exclusion_markers_are_disabled_there_shall_be_no_markers_to_look_for() {
+        let config = toml::from_str::<Config>(
+            r#"name = "test"
+output = "test"
+inputs = []
+
+[exclusion_markers]
+enabled = false
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.get_exclusion_markers(), None);
+    }
 }
@@ -0,0 +1,387 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use git2::{DiffOptions, Repository, RevparseMode};
+
+use crate::file_provider::normalize_path;
+
+/// Lines added or modified by a change, for each of its files. Paths are
+/// relative to the root of the diff, the root of the repository.
+#[derive(Debug, Default, PartialEq)]
+pub struct ChangedLines {
+    root: PathBuf,
+    files: BTreeMap<PathBuf, BTreeSet<u32>>,
+}
+
+impl ChangedLines {
+    /// Lines added by a unified diff whose paths are relative to the root of
+    /// the repository containing the working directory, or to the working
+    /// directory outside of any repository
+    pub fn from_unified_diff(diff: &str) -> Result<Self, String> {
+        let working_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+        let root = Repository::discover(&working_dir)
+            .ok()
+            .and_then(|repository| repository.workdir().map(Path::to_path_buf))
+            .unwrap_or(working_dir);
+        let root = root.canonicalize().map_err(|e| e.to_string())?;
+        Self::parse_unified_diff(diff, &normalize_path(&root))
+    }
+
+    /// Lines added by a unified diff (`git diff`, `diff -u`...), on the side of
+    /// the new files, relative to the root. Removed files and removed lines
+    /// are ignored.
+    pub fn parse_unified_diff(diff: &str, root: &Path) -> Result<Self, String> {
+        let mut files = BTreeMap::<PathBuf, BTreeSet<u32>>::new();
+        let mut current_file: Option<PathBuf> = None;
+        let mut new_line = 0;
+        let mut remaining_old_lines = 0;
+        let mut remaining_new_lines = 0;
+
+        for (index, line) in diff.lines().enumerate() {
+            // Lines of a hunk may look like headers (a removed `-- comment`)
+            if remaining_old_lines > 0 || remaining_new_lines > 0 {
+                // A line beyond the counts of the hunk header is invalid
+                let invalid_line = || format!("Invalid line {} in hunk: {}", index + 1, line);
+                let take_line =
+                    |remaining_lines: u32| remaining_lines.checked_sub(1).ok_or_else(invalid_line);
+                match line.chars().next() {
+                    Some('+') => {
+                        remaining_new_lines = take_line(remaining_new_lines)?;
+                        if let Some(file) = &current_file {
+                            files.entry(file.clone()).or_default().insert(new_line);
+                        }
+                        new_line += 1;
+                    }
+                    Some('-') => remaining_old_lines = take_line(remaining_old_lines)?,
+                    Some('\\') => {}
+                    // Some tools strip the trailing space of empty context lines
+                    Some(' ') | None => {
+                        remaining_old_lines = take_line(remaining_old_lines)?;
+                        remaining_new_lines = take_line(remaining_new_lines)?;
+                        new_line += 1;
+                    }
+                    Some(_) => return Err(invalid_line()),
+                }
+                continue;
+            }
+
+            if let Some(path) = line.strip_prefix("+++ ") {
+                current_file = Self::parse_new_file_path(path);
+                if let Some(file) = &current_file {
+                    files.entry(file.clone()).or_default();
+                }
+            } else if line.starts_with("@@ ") {
+                let (old_lines, start, new_lines) =
+                    Self::parse_hunk_header(line).ok_or_else(|| {
+                        format!("Invalid hunk header on line {}: {}", index + 1, line)
+                    })?;
+                new_line = start;
+                remaining_old_lines = old_lines;
+                remaining_new_lines = new_lines;
+            }
+        }
+
+        Ok(ChangedLines {
+            root: root.to_path_buf(),
+            files,
+        })
+    }
+
+    /// `b/src/main.rs` and `src/main.rs\t2024-01-01` both name `src/main.rs`
+    fn parse_new_file_path(path: &str) -> Option<PathBuf> {
+        let path = path.split('\t').next().unwrap_or_default().trim_end();
+        match path {
+            "/dev/null" => None,
+            _ => Some(PathBuf::from(path.strip_prefix("b/").unwrap_or(path))),
+        }
+    }
+
+    /// `@@ -12,3 +14,5 @@ fn main()` gives 3 old lines, and 5 new ones from
+    /// line 14. Counts default to 1 when omitted.
+    fn parse_hunk_header(line: &str) -> Option<(u32, u32, u32)> {
+        let mut ranges = line.strip_prefix("@@ ")?.split(' ');
+        let (_, old_lines) = Self::parse_hunk_range(ranges.next()?.strip_prefix('-')?)?;
+        let (start, new_lines) = Self::parse_hunk_range(ranges.next()?.strip_prefix('+')?)?;
+        Some((old_lines, start, new_lines))
+    }
+
+    fn parse_hunk_range(range: &str) -> Option<(u32, u32)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    }
+
+    /// Lines changed since a revision of the repository containing the
+    /// working directory. The range may be a single revision, compared to the
+    /// working tree (or to the revision the sources are read from), `A..B`, or
+    /// `A...B` to compare B to the merge base of both.
+    pub fn from_git_range(range: &str, source_rev: Option<&str>) -> Result<Self, String> {
+        let working_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+        Self::from_git_range_in(&working_dir, range, source_rev)
+    }
+
+    fn from_git_range_in(
+        working_dir: &Path,
+        range: &str,
+        source_rev: Option<&str>,
+    ) -> Result<Self, String> {
+        let repository = Repository::discover(working_dir)
+            .map_err(|e| format!("No git repository found: {}", e.message()))?;
+        let invalid_range = |e: git2::Error| format!("Invalid range {}: {}", range, e.message());
+
+        let revspec = repository.revparse(range).map_err(invalid_range)?;
+        let from = revspec.from().ok_or(format!("Invalid range {}", range))?;
+        let to = match revspec.to() {
+            Some(to) => Some(to.clone()),
+            None => source_rev
+                .map(|source_rev| repository.revparse_single(source_rev))
+                .transpose()
+                .map_err(invalid_range)?,
+        };
+        let from = match (&to, revspec.mode().contains(RevparseMode::MERGE_BASE)) {
+            (Some(to), true) => {
+                let merge_base = repository
+                    .merge_base(from.id(), to.id())
+                    .map_err(invalid_range)?;
+                repository
+                    .find_object(merge_base, None)
+                    .map_err(invalid_range)?
+            }
+            _ => from.clone(),
+        };
+
+        let from_tree = from.peel_to_tree().map_err(invalid_range)?;
+        let mut options = DiffOptions::new();
+        options.context_lines(0);
+        let mut diff = match to {
+            Some(to) => {
+                let to_tree = to.peel_to_tree().map_err(invalid_range)?;
+                repository.diff_tree_to_tree(Some(&from_tree), Some(&to_tree), Some(&mut options))
+            }
+            None => {
+                repository.diff_tree_to_workdir_with_index(Some(&from_tree), Some(&mut options))
+            }
+        }
+        .map_err(invalid_range)?;
+        diff.find_similar(None).map_err(invalid_range)?;
+
+        let workdir = repository
+            .workdir()
+            .ok_or("The git repository has no working tree")?;
+        let workdir = normalize_path(&workdir.canonicalize().map_err(|e| e.to_string())?);
+        let mut files = BTreeMap::<PathBuf, BTreeSet<u32>>::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line| {
+                if let (Some(path), Some(line_number), '+') =
+                    (delta.new_file().path(), line.new_lineno(), line.origin())
+                {
+                    files
+                        .entry(path.to_path_buf())
+                        .or_default()
+                        .insert(line_number);
+                }
+                true
+            }),
+        )
+        .map_err(invalid_range)?;
+
+        Ok(ChangedLines {
+            root: workdir,
+            files,
+        })
+    }
+
+    /// Changed lines of a file, found by its path relative to the root. A
+    /// relative path is relative to the working directory.
+    pub fn get_file_changed_lines(&self, path: &Path) -> Option<&BTreeSet<u32>> {
+        let working_dir = std::env::current_dir().unwrap_or_default();
+        let path = normalize_path(&working_dir.join(path));
+        self.files.get(path.strip_prefix(&self.root).ok()?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1a2b3c4..5d6e7f8 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
+ fn main() {
+-    run();
++    let config = load();
++    run(config);
+ }
+\\ No newline at end of file
+@@ -10 +11,0 @@ fn run() {
+--- old comment
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-fn old() {}
+diff --git a/src/lib.rs b/src/lib.rs
+new file mode 100644
+--- /dev/null
++++ b/src/lib.rs
+@@ -0,0 +1,2 @@
++mod config;
++mod run;
+";
+
+    fn lines(lines: &[u32]) -> BTreeSet<u32> {
+        lines.iter().copied().collect()
+    }
+
+    #[test]
+    fn when_parsing_a_unified_diff_added_lines_of_new_files_shall_be_changed() {
+        let changed_lines = ChangedLines::parse_unified_diff(DIFF, Path::new("/project")).unwrap();
+
+        assert_eq!(
+            changed_lines,
+            ChangedLines {
+                root: PathBuf::from("/project"),
+                files: BTreeMap::from([
+                    (PathBuf::from("src/lib.rs"), lines(&[1, 2])),
+                    (PathBuf::from("src/main.rs"), lines(&[2, 3])),
+                ])
+            }
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_diff_without_prefixes_nor_counts_paths_and_lines_shall_be_read() {
+        let diff = "--- main.c\t2024-01-01 10:00:00\n+++ main.c\t2024-01-02 10:00:00\n@@ -3 +3 @@\n-int a;\n+int b;\n";
+
+        assert_eq!(
+            ChangedLines::parse_unified_diff(diff, Path::new("/project")).unwrap(),
+            ChangedLines {
+                root: PathBuf::from("/project"),
+                files: BTreeMap::from([(PathBuf::from("main.c"), lines(&[3]))])
+            }
+        );
+    }
+
+    #[test]
+    fn when_parsing_an_invalid_hunk_header_it_shall_fail() {
+        assert_eq!(
+            ChangedLines::parse_unified_diff("+++ b/main.c\n@@ -a +b @@\n", Path::new("/project")),
+            Err("Invalid hunk header on line 2: @@ -a +b @@".to_string())
+        );
+    }
+
+    #[test]
+    fn when_a_hunk_has_more_lines_than_its_header_counts_it_shall_fail() {
+        assert_eq!(
+            ChangedLines::parse_unified_diff(
+                "+++ b/main.c\n@@ -1,0 +1,1 @@\n-bad\n+line\n",
+                Path::new("/project")
+            ),
+            Err("Invalid line 3 in hunk: -bad".to_string())
+        );
+    }
+
+    #[test]
+    fn when_matching_a_file_its_path_relative_to_the_root_shall_be_the_one_of_the_diff() {
+        let changed_lines = ChangedLines {
+            root: PathBuf::from("/project"),
+            files: BTreeMap::from([
+                (PathBuf::from("main.rs"), lines(&[1])),
+                (PathBuf::from("src/main.rs"), lines(&[2])),
+            ]),
+        };
+
+        assert_eq!(
+            changed_lines.get_file_changed_lines(Path::new("/project/src/main.rs")),
+            Some(&lines(&[2]))
+        );
+        assert_eq!(
+            changed_lines.get_file_changed_lines(Path::new("/project/src/../main.rs")),
+            Some(&lines(&[1]))
+        );
+        assert_eq!(
+            changed_lines.get_file_changed_lines(Path::new("/project/lib/main.rs")),
+            None
+        );
+        assert_eq!(
+            changed_lines.get_file_changed_lines(Path::new("/elsewhere/src/main.rs")),
+            None
+        );
+    }
+
+    /// Repository with two commits changing `src/main.rs`, changed again in
+    /// the working tree
+    fn init_repository(dir: &Path) {
+        let repository = Repository::init(dir).unwrap();
+        let signature = git2::Signature::now("me", "me@example.com").unwrap();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+
+        let mut parents = vec![];
+        for (tag, content) in [("v1", "a\nb\nc\n"), ("v2", "a\nB\nc\nd\n")] {
+            std::fs::write(dir.join("src/main.rs"), content).unwrap();
+            let mut index = repository.index().unwrap();
+            index.add_path(Path::new("src/main.rs")).unwrap();
+            index.write().unwrap();
+            let tree = repository.find_tree(index.write_tree().unwrap()).unwrap();
+            let parent_commits = parents
+                .iter()
+                .map(|id| repository.find_commit(*id).unwrap())
+                .collect::<Vec<_>>();
+            let commit = repository
+                .commit(
+                    Some("HEAD"),
+                    &signature,
+                    &signature,
+                    tag,
+                    &tree,
+                    &parent_commits.iter().collect::<Vec<_>>(),
+                )
+                .unwrap();
+            repository
+                .tag_lightweight(tag, &repository.find_object(commit, None).unwrap(), false)
+                .unwrap();
+            parents = vec![commit];
+        }
+        std::fs::write(dir.join("src/main.rs"), "A\nB\nc\nd\n").unwrap();
+    }
+
+    #[test]
+    fn when_computing_changed_lines_from_a_git_range_they_shall_be_the_added_ones() {
+        let dir = std::env::temp_dir().join(format!("lcoviz-diff-range-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.canonicalize().unwrap();
+        init_repository(&dir);
+        let main = dir.join("src/main.rs");
+
+        let since_v1 = ChangedLines::from_git_range_in(&dir, "v1", None).unwrap();
+        assert_eq!(
+            since_v1.get_file_changed_lines(&main),
+            Some(&lines(&[1, 2, 4]))
+        );
+
+        let v1_to_v2 = ChangedLines::from_git_range_in(&dir, "v1..v2", None).unwrap();
+        assert_eq!(
+            v1_to_v2.get_file_changed_lines(&main),
+            Some(&lines(&[2, 4]))
+        );
+
+        let v1_to_source_rev = ChangedLines::from_git_range_in(&dir, "v1", Some("v2")).unwrap();
+        assert_eq!(v1_to_source_rev, v1_to_v2);
+
+        assert!(ChangedLines::from_git_range_in(&dir, "v3", None)
+            .unwrap_err()
+            .starts_with("Invalid range v3: "));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
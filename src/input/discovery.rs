@@ -0,0 +1,329 @@
+use std::path::{Path, PathBuf};
+
+use globset::GlobBuilder;
+
+use crate::{core::FileSystem, file_provider::normalize_path};
+
+use super::{
+    config::{Input, InputFormat},
+    errors::InputError,
+};
+
+const GLOB_CHARACTERS: [char; 4] = ['*', '?', '[', '{'];
+const LCOV_EXTENSIONS: [&str; 2] = [".info", ".lcov"];
+const COMPRESSION_EXTENSIONS: [&str; 4] = [".gz", ".gzip", ".zst", ".zstd"];
+
+/// Replace inputs whose path is a glob pattern, or a directory containing LCOV
+/// files, by one input per matching file. Other inputs are kept as is.
+/// When `keep_going` is set, inputs that can't be expanded are skipped, and
+/// their errors are returned along with the expanded inputs.
+pub fn expand_inputs(
+    inputs: Vec<Input>,
+    fs: &impl FileSystem,
+    keep_going: bool,
+) -> Result<(Vec<Input>, Vec<InputError>), InputError> {
+    let mut expanded_inputs = vec![];
+    let mut skipped_inputs = vec![];
+    for input in inputs {
+        match expand_input(input, fs) {
+            Ok(inputs) => expanded_inputs.extend(inputs),
+            Err(e) if keep_going => skipped_inputs.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((expanded_inputs, skipped_inputs))
+}
+
+fn expand_input(input: Input, fs: &impl FileSystem) -> Result<Vec<Input>, InputError> {
+    if is_glob_pattern(&input.path) {
+        let base = get_glob_base(&input.path);
+        let matches = find_glob_matches(&input.path, &base, fs)
+            .map_err(|e| InputError::discovery(&input, e))?;
+        if matches.is_empty() {
+            return Err(InputError::discovery(
+                &input,
+                format!("No file matches the pattern '{}'", input.path.display()),
+            ));
+        }
+        return Ok(matches
+            .into_iter()
+            .map(|path| input_for_match(&input, &base, path))
+            .collect());
+    }
+
+    // gcov writes one report per translation unit, a directory of them is a
+    // single input
+    if input.format != Some(InputFormat::GcovJson) && fs.is_dir(&input.path) {
+        let matches = fs
+            .list_files_recursively(&input.path)
+            .map_err(|e| InputError::read(&input, input.path.clone(), e))?
+            .into_iter()
+            .filter(|path| is_lcov_file(path))
+            .collect::<Vec<_>>();
+        if !matches.is_empty() {
+            return Ok(matches
+                .into_iter()
+                .map(|path| input_for_match(&input, &input.path, path))
+                .collect());
+        }
+    }
+
+    Ok(vec![input])
+}
+
+fn is_glob_pattern(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|path| path.contains(GLOB_CHARACTERS))
+}
+
+/// The longest leading part of the pattern without any glob character, from
+/// which matching files are searched for
+fn get_glob_base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| !is_glob_pattern(Path::new(component.as_os_str())))
+        .collect()
+}
+
+fn find_glob_matches(
+    pattern: &Path,
+    base: &Path,
+    fs: &impl FileSystem,
+) -> Result<Vec<PathBuf>, String> {
+    let pattern = pattern.to_str().ok_or("Invalid glob pattern")?;
+    let matcher = GlobBuilder::new(pattern.strip_prefix("./").unwrap_or(pattern))
+        .literal_separator(true)
+        .build()
+        .map_err(|e| e.to_string())?
+        .compile_matcher();
+
+    let search_directory = match base.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => base,
+    };
+    if !fs.is_dir(search_directory) {
+        return Ok(vec![]);
+    }
+
+    Ok(fs
+        .list_files_recursively(search_directory)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix("./")
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .filter(|path| matcher.is_match(path))
+        .collect())
+}
+
+fn is_lcov_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let name = COMPRESSION_EXTENSIONS
+        .iter()
+        .find_map(|ext| name.strip_suffix(ext))
+        .unwrap_or(name);
+    LCOV_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Each matching file is named after its directory, relatively to the base of
+/// the search, unless the input was explicitly named. Both are normalized, for
+/// `./` not to make a difference.
+fn input_for_match(input: &Input, base: &Path, path: PathBuf) -> Input {
+    let name = input.name.clone().or_else(|| {
+        let directory = normalize_path(path.parent()?);
+        let directory = directory
+            .strip_prefix(normalize_path(base))
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .unwrap_or(&directory);
+        Some(directory.to_str()?.to_string()).filter(|name| !name.is_empty())
+    });
+
+    Input {
+        name,
+        path,
+        ..input.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::core::MockFileSystem;
+
+    fn get_fs(files: &'static [&'static str]) -> MockFileSystem {
+        let mut fs = MockFileSystem::new();
+        fs.expect_is_dir()
+            .returning(|path| path.extension().is_none());
+        fs.expect_list_files_recursively().returning(|directory| {
+            Ok(files
+                .iter()
+                .map(PathBuf::from)
+                .filter(|file| file.starts_with(directory))
+                .collect())
+        });
+        fs
+    }
+
+    fn get_names_and_paths(inputs: &[Input]) -> Vec<(Option<&str>, &str)> {
+        inputs
+            .iter()
+            .map(|input| (input.name.as_deref(), input.path.to_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn when_input_is_a_file_it_shall_be_kept_as_is() {
+        let fs = get_fs(&[]);
+        let inputs = expand_inputs(vec![Input::from_path("lcov.info".into())], &fs, false)
+            .unwrap()
+            .0;
+        assert_eq!(inputs, vec![Input::from_path("lcov.info".into())]);
+    }
+
+    #[test]
+    fn when_input_is_a_glob_pattern_each_match_shall_be_an_input_named_after_its_directory() {
+        let fs = get_fs(&[
+            "crates/a/target/lcov.info",
+            "crates/b/target/lcov.info",
+            "crates/b/target/other.txt",
+            "crates/c/lcov.info",
+        ]);
+        let inputs = expand_inputs(
+            vec![Input::from_path("crates/*/target/lcov.info".into())],
+            &fs,
+            false,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![
+                (Some("a/target"), "crates/a/target/lcov.info"),
+                (Some("b/target"), "crates/b/target/lcov.info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_glob_pattern_starts_with_a_wildcard_it_shall_search_current_directory() {
+        let fs = get_fs(&["./a/lcov.info", "./b/c/lcov.info", "./lcov.info"]);
+        let inputs = expand_inputs(vec![Input::from_path("**/lcov.info".into())], &fs, false)
+            .unwrap()
+            .0;
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![
+                (Some("a"), "a/lcov.info"),
+                (Some("b/c"), "b/c/lcov.info"),
+                (None, "lcov.info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_glob_pattern_starts_with_the_current_directory_inputs_shall_be_named_the_same() {
+        let fs = get_fs(&["./crates/a/target/lcov.info", "./crates/b/lcov.info"]);
+        let inputs = expand_inputs(
+            vec![Input::from_path("./crates/*/**/lcov.info".into())],
+            &fs,
+            false,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![
+                (Some("a/target"), "crates/a/target/lcov.info"),
+                (Some("b"), "crates/b/lcov.info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_glob_pattern_matches_nothing_it_shall_fail() {
+        let fs = get_fs(&["crates/a/lcov.info"]);
+        assert_eq!(
+            expand_inputs(
+                vec![Input::from_path("crates/*/cov.info".into())],
+                &fs,
+                false
+            )
+            .unwrap_err()
+            .to_string(),
+            "Input 'crates/*/cov.info': No file matches the pattern 'crates/*/cov.info'"
+        );
+    }
+
+    #[test]
+    fn when_input_is_a_directory_it_shall_be_scanned_for_lcov_files() {
+        let fs = get_fs(&[
+            "coverage/unit/lcov.info",
+            "coverage/e2e/report.lcov.gz",
+            "coverage/e2e/index.html",
+            "coverage/all.info",
+        ]);
+        let inputs = expand_inputs(vec![Input::from_path("coverage".into())], &fs, false)
+            .unwrap()
+            .0;
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![
+                (Some("unit"), "coverage/unit/lcov.info"),
+                (Some("e2e"), "coverage/e2e/report.lcov.gz"),
+                (Some("coverage"), "coverage/all.info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_a_named_input_is_expanded_all_matches_shall_keep_its_name() {
+        let fs = get_fs(&["coverage/unit/lcov.info", "coverage/e2e/lcov.info"]);
+        let inputs = expand_inputs(
+            vec![Input::from_name_and_path("App".into(), "coverage".into())],
+            &fs,
+            false,
+        )
+        .unwrap()
+        .0;
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![
+                (Some("App"), "coverage/unit/lcov.info"),
+                (Some("App"), "coverage/e2e/lcov.info"),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_keeping_going_inputs_that_cannot_be_expanded_shall_be_skipped() {
+        let fs = get_fs(&["crates/a/lcov.info"]);
+        let (inputs, skipped) = expand_inputs(
+            vec![
+                Input::from_path("crates/*/cov.info".into()),
+                Input::from_path("crates/*/lcov.info".into()),
+            ],
+            &fs,
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            get_names_and_paths(&inputs),
+            vec![(Some("a"), "crates/a/lcov.info")]
+        );
+        assert_eq!(skipped.len(), 1);
+    }
+
+    #[test]
+    fn when_directory_does_not_contain_lcov_files_it_shall_be_kept_as_is() {
+        let fs = get_fs(&["build/main.gcov.json.gz"]);
+        let inputs = expand_inputs(vec![Input::from_path("build".into())], &fs, false)
+            .unwrap()
+            .0;
+        assert_eq!(inputs, vec![Input::from_path("build".into())]);
+    }
+}
@@ -0,0 +1,138 @@
+use std::{fmt::Display, path::PathBuf};
+
+use super::config::Input;
+
+/// Errors that may happen while loading an input. Each of them names the
+/// input it comes from, so that it can be found among many others.
+#[derive(Debug, PartialEq)]
+pub enum InputError {
+    /// The report of the input could not be read (missing file, invalid
+    /// compressed data...)
+    Read {
+        input: String,
+        path: PathBuf,
+        cause: String,
+    },
+
+    /// The report of the input is not valid in its format. For line based
+    /// formats like LCOV, the line of the error is known.
+    Parse {
+        input: String,
+        path: PathBuf,
+        line: Option<usize>,
+        cause: String,
+    },
+
+    /// Some tested files of the input are outside of its prefix
+    Prefix { input: String, cause: String },
+
+    /// No report could be found for the input (glob pattern without match...)
+    Discovery { input: String, cause: String },
+
+    /// The include or exclude patterns of the input are invalid
+    Filter { input: String, cause: String },
+}
+impl InputError {
+    pub fn read(input: &Input, path: PathBuf, cause: impl ToString) -> Self {
+        InputError::Read {
+            input: input.get_display_name(),
+            path,
+            cause: cause.to_string(),
+        }
+    }
+
+    pub fn parse(input: &Input, line: Option<usize>, cause: impl ToString) -> Self {
+        InputError::Parse {
+            input: input.get_display_name(),
+            path: input.path.clone(),
+            line,
+            cause: cause.to_string(),
+        }
+    }
+
+    pub fn prefix(input: &Input, cause: impl ToString) -> Self {
+        InputError::Prefix {
+            input: input.get_display_name(),
+            cause: cause.to_string(),
+        }
+    }
+
+    pub fn discovery(input: &Input, cause: impl ToString) -> Self {
+        InputError::Discovery {
+            input: input.get_display_name(),
+            cause: cause.to_string(),
+        }
+    }
+
+    pub fn filter(input: &Input, cause: impl ToString) -> Self {
+        InputError::Filter {
+            input: input.get_display_name(),
+            cause: cause.to_string(),
+        }
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Read { input, path, cause } => write!(
+                f,
+                "Input '{}': cannot read {}: {}",
+                input,
+                path.display(),
+                cause
+            ),
+            InputError::Parse {
+                input,
+                path,
+                line: Some(line),
+                cause,
+            } => write!(
+                f,
+                "Input '{}': invalid report {} at line {}: {}",
+                input,
+                path.display(),
+                line,
+                cause
+            ),
+            InputError::Parse {
+                input,
+                path,
+                line: None,
+                cause,
+            } => write!(
+                f,
+                "Input '{}': invalid report {}: {}",
+                input,
+                path.display(),
+                cause
+            ),
+            InputError::Prefix { input, cause }
+            | InputError::Discovery { input, cause }
+            | InputError::Filter { input, cause } => write!(f, "Input '{}': {}", input, cause),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn when_displaying_a_parse_error_it_shall_name_the_input_path_and_line() {
+        let input = Input::from_name_and_path("Backend".into(), "backend/lcov.info".into());
+        assert_eq!(
+            InputError::parse(&input, Some(12), "invalid number").to_string(),
+            "Input 'Backend': invalid report backend/lcov.info at line 12: invalid number"
+        );
+    }
+
+    #[test]
+    fn when_displaying_an_error_of_an_unnamed_input_it_shall_be_named_after_its_path() {
+        let input = Input::from_path("backend/lcov.info".into());
+        assert_eq!(
+            InputError::read(&input, input.path.clone(), "No such file or directory").to_string(),
+            "Input 'backend/lcov.info': cannot read backend/lcov.info: No such file or directory"
+        );
+    }
+}
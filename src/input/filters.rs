@@ -0,0 +1,114 @@
+use std::{collections::BTreeMap, path::Path};
+
+use globset::{Glob, GlobMatcher};
+
+/// Number of files removed by each filter, the filters being named after
+/// their patterns
+pub type RemovedFiles = BTreeMap<String, usize>;
+
+/// Keep only the tested files matching one of the include patterns (if any),
+/// and none of the exclude patterns. Like `lcov --remove`, a `*` also matches
+/// `/` so that `*.pb.go` matches generated files in any directory.
+pub struct FileFilter {
+    include: Option<(String, Vec<GlobMatcher>)>,
+    exclude: Vec<(String, GlobMatcher)>,
+}
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        let include = match include.is_empty() {
+            true => None,
+            false => Some((
+                format!("include {}", Self::quote_patterns(include)),
+                include
+                    .iter()
+                    .map(|pattern| Self::compile(pattern))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+        };
+        let exclude = exclude
+            .iter()
+            .map(|pattern| {
+                Self::compile(pattern).map(|matcher| (format!("exclude '{}'", pattern), matcher))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { include, exclude })
+    }
+
+    fn compile(pattern: &str) -> Result<GlobMatcher, String> {
+        Glob::new(pattern)
+            .map(|glob| glob.compile_matcher())
+            .map_err(|e| format!("Invalid filter pattern '{}': {}", pattern, e))
+    }
+
+    fn quote_patterns(patterns: &[String]) -> String {
+        patterns
+            .iter()
+            .map(|pattern| format!("'{}'", pattern))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_empty()
+    }
+
+    /// Name of the filter removing the file, if it is removed
+    pub fn get_removing_filter(&self, path: &Path) -> Option<&str> {
+        if let Some((name, matchers)) = &self.include {
+            if !matchers.iter().any(|matcher| matcher.is_match(path)) {
+                return Some(name);
+            }
+        }
+        self.exclude
+            .iter()
+            .find(|(_, matcher)| matcher.is_match(path))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_strings(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn when_file_matches_an_exclude_pattern_it_shall_be_removed_by_it() {
+        let filter = FileFilter::new(&[], &to_strings(&["**/vendor/**", "*.pb.go"])).unwrap();
+        assert_eq!(
+            filter.get_removing_filter(Path::new("/project/vendor/lib/lib.go")),
+            Some("exclude '**/vendor/**'")
+        );
+        assert_eq!(
+            filter.get_removing_filter(Path::new("/project/api/api.pb.go")),
+            Some("exclude '*.pb.go'")
+        );
+        assert_eq!(
+            filter.get_removing_filter(Path::new("/project/api/api.go")),
+            None
+        );
+    }
+
+    #[test]
+    fn when_file_matches_no_include_pattern_it_shall_be_removed() {
+        let filter = FileFilter::new(&to_strings(&["**/src/**", "**/lib/**"]), &[]).unwrap();
+        assert_eq!(
+            filter.get_removing_filter(Path::new("/project/tests/helpers.rs")),
+            Some("include '**/src/**', '**/lib/**'")
+        );
+        assert_eq!(
+            filter.get_removing_filter(Path::new("/project/lib/lib.rs")),
+            None
+        );
+    }
+
+    #[test]
+    fn when_pattern_is_invalid_it_shall_fail() {
+        assert!(FileFilter::new(&[], &to_strings(&["src/[a"]))
+            .err()
+            .unwrap()
+            .starts_with("Invalid filter pattern 'src/[a': "));
+    }
+}
@@ -0,0 +1,355 @@
+use std::path::PathBuf;
+
+use lcov::report::section::{
+    branch::{Key as BranchKey, Value as BranchValue},
+    function::{Key as FunctionKey, Value as FunctionValue},
+    line::Key as LineKey,
+    Key as SectionKey, Value as SectionValue,
+};
+use lcov::Report as LcovReport;
+use roxmltree::Node;
+
+use super::xml::{children, parse_attribute, parse_document};
+
+/// Parse a Cobertura XML report (as produced by pytest-cov, coverlet, gcovr...)
+/// into the same structure as an LCOV report, one section per source file.
+pub fn parse(content: &str) -> Result<LcovReport, String> {
+    parse_report(content).map_err(|e| format!("Invalid Cobertura report: {}", e))
+}
+
+fn parse_report(content: &str) -> Result<LcovReport, String> {
+    let document = parse_document(content)?;
+
+    let root = document.root_element();
+    if !root.has_tag_name("coverage") {
+        return Err(format!(
+            "expected a <coverage> root element, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let sources = root
+        .descendants()
+        .filter(|node| node.has_tag_name("source"))
+        .filter_map(|node| node.text())
+        .map(|text| PathBuf::from(text.trim()))
+        .filter(|source| !source.as_os_str().is_empty())
+        .collect::<Vec<_>>();
+
+    let mut report = LcovReport::new();
+    for class in root.descendants().filter(|node| node.has_tag_name("class")) {
+        let file_name = class
+            .attribute("filename")
+            .ok_or("<class> without filename")?;
+
+        let key = SectionKey {
+            test_name: String::new(),
+            source_file: resolve_source_file(&sources, file_name),
+        };
+        let section = report.sections.entry(key).or_default();
+        add_class(section, class)?;
+    }
+
+    Ok(report)
+}
+
+/// Relative file names are resolved against the first source containing them,
+/// or the first source when none does (reports moved from where they were made)
+fn resolve_source_file(sources: &[PathBuf], file_name: &str) -> PathBuf {
+    let file_path = PathBuf::from(file_name);
+    if file_path.is_absolute() {
+        return file_path;
+    }
+    sources
+        .iter()
+        .map(|source| source.join(&file_path))
+        .find(|path| path.exists())
+        .or_else(|| sources.first().map(|source| source.join(&file_path)))
+        .unwrap_or(file_path)
+}
+
+/// Extract the (covered, total) pair from a condition-coverage attribute such as "50% (1/2)"
+fn parse_condition_coverage(condition_coverage: &str) -> Option<(u32, u32)> {
+    let start = condition_coverage.find('(')?;
+    let end = condition_coverage.find(')')?;
+    let (covered, total) = condition_coverage.get(start + 1..end)?.split_once('/')?;
+    Some((covered.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+/// Lines repeated in several classes of the same file (inner classes, template
+/// instances...) keep their highest counts rather than adding them up
+fn add_line(section: &mut SectionValue, line: Node) -> Result<(), String> {
+    let line_number: u32 = parse_attribute(line, "number")?;
+    let hits: u64 = parse_attribute(line, "hits")?;
+
+    let value = section
+        .lines
+        .entry(LineKey { line: line_number })
+        .or_default();
+    value.count = value.count.max(hits);
+
+    if line.attribute("branch") != Some("true") {
+        return Ok(());
+    }
+
+    let Some((covered, total)) = line
+        .attribute("condition-coverage")
+        .and_then(parse_condition_coverage)
+    else {
+        return Ok(());
+    };
+
+    for branch in 0..total {
+        let taken = match hits {
+            0 => None,
+            _ if branch < covered => Some(1),
+            _ => Some(0),
+        };
+        let value = section
+            .branches
+            .entry(BranchKey {
+                line: line_number,
+                block: 0,
+                branch,
+            })
+            .or_insert(BranchValue { taken });
+        value.taken = value.taken.max(taken);
+    }
+
+    Ok(())
+}
+
+fn add_method(section: &mut SectionValue, method: Node) -> Result<(), String> {
+    let name = method.attribute("name").ok_or("<method> without name")?;
+
+    let mut first_line: Option<(u32, u64)> = None;
+    for line in children(method, "lines").flat_map(|lines| children(lines, "line")) {
+        let line_number: u32 = parse_attribute(line, "number")?;
+        let hits: u64 = parse_attribute(line, "hits")?;
+        if first_line.is_none_or(|(first, _)| line_number < first) {
+            first_line = Some((line_number, hits));
+        }
+    }
+
+    let value = section
+        .functions
+        .entry(FunctionKey {
+            name: name.to_string(),
+        })
+        .or_insert(FunctionValue {
+            start_line: first_line.map(|(line, _)| line),
+            count: 0,
+        });
+    value.count = value.count.max(first_line.map_or(0, |(_, hits)| hits));
+
+    Ok(())
+}
+
+fn add_class(section: &mut SectionValue, class: Node) -> Result<(), String> {
+    for line in children(class, "lines").flat_map(|lines| children(lines, "line")) {
+        add_line(section, line)?;
+    }
+
+    for method in children(class, "methods").flat_map(|methods| children(methods, "method")) {
+        add_method(section, method)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" ?>
+<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+<coverage line-rate="0.75" branch-rate="0.5" version="7.4.4" timestamp="1713000000">
+    <sources>
+        <source>/home/user/project</source>
+    </sources>
+    <packages>
+        <package name="pkg" line-rate="0.75" branch-rate="0.5">
+            <classes>
+                <class name="module.py" filename="pkg/module.py" line-rate="0.75">
+                    <methods>
+                        <method name="add" signature="(a, b)" line-rate="1">
+                            <lines>
+                                <line number="2" hits="3"/>
+                                <line number="3" hits="3"/>
+                            </lines>
+                        </method>
+                        <method name="never" signature="()" line-rate="0">
+                            <lines>
+                                <line number="6" hits="0"/>
+                            </lines>
+                        </method>
+                    </methods>
+                    <lines>
+                        <line number="1" hits="1"/>
+                        <line number="2" hits="3"/>
+                        <line number="3" hits="3" branch="true" condition-coverage="50% (1/2)"/>
+                        <line number="6" hits="0"/>
+                    </lines>
+                </class>
+            </classes>
+        </package>
+    </packages>
+</coverage>"#;
+
+    fn get_section(report: &LcovReport, source_file: &str) -> SectionValue {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from(source_file),
+            })
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn when_parsing_a_report_each_class_shall_become_a_section_relative_to_source() {
+        let report = parse(REPORT).unwrap();
+        assert_eq!(report.sections.len(), 1);
+        get_section(&report, "/home/user/project/pkg/module.py");
+    }
+
+    #[test]
+    fn when_parsing_a_report_lines_shall_have_their_hit_count() {
+        let report = parse(REPORT).unwrap();
+        let section = get_section(&report, "/home/user/project/pkg/module.py");
+
+        let lines = section
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(1, 1), (2, 3), (3, 3), (6, 0)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_methods_shall_become_functions_starting_at_their_first_line() {
+        let report = parse(REPORT).unwrap();
+        let section = get_section(&report, "/home/user/project/pkg/module.py");
+
+        let functions = section
+            .functions
+            .iter()
+            .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(functions, vec![("add", Some(2), 3), ("never", Some(6), 0)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_condition_coverage_shall_become_branches() {
+        let report = parse(REPORT).unwrap();
+        let section = get_section(&report, "/home/user/project/pkg/module.py");
+
+        let branches = section
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(branches, vec![(3, 0, Some(1)), (3, 1, Some(0))]);
+    }
+
+    #[test]
+    fn when_several_classes_share_a_file_they_shall_be_merged_in_one_section() {
+        let report = parse(
+            r#"<coverage><packages><package><classes>
+<class filename="/abs/File.java"><lines><line number="1" hits="1"/></lines></class>
+<class filename="/abs/File.java"><lines><line number="4" hits="0"/></lines></class>
+</classes></package></packages></coverage>"#,
+        )
+        .unwrap();
+
+        assert_eq!(report.sections.len(), 1);
+        assert_eq!(get_section(&report, "/abs/File.java").lines.len(), 2);
+    }
+
+    #[test]
+    fn when_several_classes_share_a_line_its_highest_count_shall_be_kept() {
+        let report = parse(
+            r#"<coverage><packages><package><classes>
+<class filename="/abs/File.java"><lines>
+    <line number="1" hits="2"/>
+    <line number="2" hits="0" branch="true" condition-coverage="0% (0/2)"/>
+</lines></class>
+<class filename="/abs/File.java"><lines>
+    <line number="1" hits="3"/>
+    <line number="2" hits="1" branch="true" condition-coverage="50% (1/2)"/>
+</lines></class>
+</classes></package></packages></coverage>"#,
+        )
+        .unwrap();
+        let section = get_section(&report, "/abs/File.java");
+
+        let lines = section
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(1, 3), (2, 1)]);
+        let branches = section
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(branches, vec![(2, 0, Some(1)), (2, 1, Some(0))]);
+    }
+
+    #[test]
+    fn when_a_report_has_several_sources_files_shall_be_found_in_the_one_containing_them() {
+        let dir = std::env::temp_dir().join(format!("lcoviz-cobertura-{}", std::process::id()));
+        let (first, second) = (dir.join("first"), dir.join("second"));
+        std::fs::create_dir_all(second.join("pkg")).unwrap();
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::write(second.join("pkg/module.py"), "").unwrap();
+
+        let report = parse(&format!(
+            r#"<coverage><sources><source>{}</source><source>{}</source></sources>
+<packages><package><classes>
+<class filename="pkg/module.py"><lines><line number="1" hits="1"/></lines></class>
+<class filename="pkg/missing.py"><lines><line number="1" hits="1"/></lines></class>
+</classes></package></packages></coverage>"#,
+            first.display(),
+            second.display()
+        ))
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let source_files = report
+            .sections
+            .keys()
+            .map(|key| key.source_file.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            source_files,
+            vec![first.join("pkg/missing.py"), second.join("pkg/module.py")]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_non_cobertura_document_it_shall_fail() {
+        assert_eq!(
+            parse("<report name=\"jacoco\"></report>").unwrap_err(),
+            "Invalid Cobertura report: expected a <coverage> root element, found <report>"
+        );
+    }
+
+    #[test]
+    fn when_a_line_has_no_hits_attribute_it_shall_fail() {
+        assert_eq!(
+            parse(r#"<coverage><class filename="a.py"><lines><line number="1"/></lines></class></coverage>"#)
+                .unwrap_err(),
+            "Invalid Cobertura report: <line> without hits"
+        );
+    }
+
+    #[test]
+    fn condition_coverage_shall_be_parsed() {
+        assert_eq!(parse_condition_coverage("50% (1/2)"), Some((1, 2)));
+        assert_eq!(parse_condition_coverage("100% (4/4)"), Some((4, 4)));
+        assert_eq!(parse_condition_coverage("50%"), None);
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use lcov::report::section::{
+    branch::{Key as BranchKey, Value as BranchValue},
+    function::{Key as FunctionKey, Value as FunctionValue},
+    line::Key as LineKey,
+    Key as SectionKey, Value as SectionValue,
+};
+use lcov::Report as LcovReport;
+use serde::Deserialize;
+
+use crate::core::FunctionEndLines;
+
+pub const GCC_VERSION_KEY: &str = "\"gcc_version\"";
+pub const FILE_EXTENSIONS: [&str; 2] = [".gcov.json", ".gcov.json.gz"];
+
+#[derive(Deserialize)]
+struct GcovReport {
+    #[serde(default)]
+    current_working_directory: Option<PathBuf>,
+    #[serde(default)]
+    files: Vec<File>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    file: PathBuf,
+    #[serde(default)]
+    functions: Vec<Function>,
+    #[serde(default)]
+    lines: Vec<Line>,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    name: String,
+    demangled_name: Option<String>,
+    start_line: u32,
+    end_line: Option<u32>,
+    execution_count: u64,
+}
+
+#[derive(Deserialize)]
+struct Line {
+    line_number: u32,
+    count: u64,
+    #[serde(default)]
+    branches: Vec<Branch>,
+}
+
+#[derive(Deserialize)]
+struct Branch {
+    count: u64,
+}
+
+/// Tell whether a file name is one of those written by `gcov --json-format`
+pub fn is_gcov_json_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| FILE_EXTENSIONS.iter().any(|ext| name.ends_with(ext)))
+}
+
+/// Parse the JSON intermediate format of gcov (one file per translation unit)
+/// into an LCOV report, one section per source file, along with the last line
+/// of its functions.
+pub fn parse(content: &str) -> Result<(LcovReport, FunctionEndLines), String> {
+    parse_report(content).map_err(|e| format!("Invalid gcov JSON report: {}", e))
+}
+
+/// Parse the reports of several translation units and merge them into a single
+/// one. Headers included by several translation units are merged into a single
+/// section, summing their hit counts.
+pub fn parse_all<'a>(
+    contents: impl Iterator<Item = &'a str>,
+) -> Result<(LcovReport, FunctionEndLines), String> {
+    let mut report = LcovReport::new();
+    let mut function_end_lines = FunctionEndLines::new();
+    for content in contents {
+        let (other_report, other_end_lines) = parse(content)?;
+        report.merge_lossy(other_report);
+        for (source_file, end_lines) in other_end_lines {
+            function_end_lines
+                .entry(source_file)
+                .or_default()
+                .extend(end_lines);
+        }
+    }
+    Ok((report, function_end_lines))
+}
+
+fn parse_report(content: &str) -> Result<(LcovReport, FunctionEndLines), String> {
+    let gcov_report = serde_json::from_str::<GcovReport>(content).map_err(|e| e.to_string())?;
+
+    let mut report = LcovReport::new();
+    let mut function_end_lines = FunctionEndLines::new();
+    for file in gcov_report.files {
+        let key = SectionKey {
+            test_name: String::new(),
+            source_file: resolve_source_file(
+                gcov_report.current_working_directory.as_deref(),
+                &file.file,
+            ),
+        };
+        let end_lines = function_end_lines
+            .entry(key.source_file.clone())
+            .or_default();
+        let section = report.sections.entry(key).or_default();
+        add_lines(section, &file.lines);
+        add_functions(section, end_lines, file.functions);
+    }
+
+    Ok((report, function_end_lines))
+}
+
+fn resolve_source_file(working_directory: Option<&Path>, file: &Path) -> PathBuf {
+    match working_directory {
+        Some(directory) if file.is_relative() => directory.join(file),
+        _ => file.to_path_buf(),
+    }
+}
+
+/// A line may be listed several times in a translation unit, once for each
+/// function instantiated on it (templates, inlined functions...). Hits are
+/// summed, and the branches of each instance get their own block.
+fn add_lines(section: &mut SectionValue, lines: &[Line]) {
+    for line in lines {
+        let value = section
+            .lines
+            .entry(LineKey {
+                line: line.line_number,
+            })
+            .or_default();
+        value.count = value.count.saturating_add(line.count);
+
+        let block = section
+            .branches
+            .keys()
+            .filter(|key| key.line == line.line_number)
+            .map(|key| key.block + 1)
+            .max()
+            .unwrap_or(0);
+        for (index, branch) in line.branches.iter().enumerate() {
+            section.branches.insert(
+                BranchKey {
+                    line: line.line_number,
+                    block,
+                    branch: index as u32,
+                },
+                BranchValue {
+                    taken: (line.count > 0).then_some(branch.count),
+                },
+            );
+        }
+    }
+}
+
+fn add_functions(
+    section: &mut SectionValue,
+    end_lines: &mut BTreeMap<String, u32>,
+    functions: Vec<Function>,
+) {
+    for function in functions {
+        let name = function.demangled_name.unwrap_or(function.name);
+        if let Some(end_line) = function.end_line {
+            end_lines.insert(name.clone(), end_line);
+        }
+        let value = section
+            .functions
+            .entry(FunctionKey { name })
+            .or_insert(FunctionValue {
+                start_line: Some(function.start_line),
+                count: 0,
+            });
+        value.count = value.count.saturating_add(function.execution_count);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAIN_TU: &str = r#"{
+  "format_version": "1",
+  "gcc_version": "13.2.0",
+  "current_working_directory": "/project",
+  "data_file": "build/main.gcda",
+  "files": [
+    {
+      "file": "src/main.cpp",
+      "functions": [
+        {"name": "main", "demangled_name": "main", "start_line": 3, "start_column": 5, "end_line": 7, "end_column": 1, "blocks": 4, "blocks_executed": 3, "execution_count": 1}
+      ],
+      "lines": [
+        {"line_number": 3, "count": 1, "unexecuted_block": false, "function_name": "main", "branches": []},
+        {"line_number": 4, "count": 1, "unexecuted_block": true, "function_name": "main", "branches": [
+          {"count": 1, "fallthrough": true, "throw": false},
+          {"count": 0, "fallthrough": false, "throw": false}
+        ]},
+        {"line_number": 5, "count": 0, "unexecuted_block": true, "function_name": "main", "branches": [
+          {"count": 0, "fallthrough": true, "throw": false}
+        ]}
+      ]
+    },
+    {
+      "file": "/project/include/util.hpp",
+      "functions": [
+        {"name": "_Z6squarei", "demangled_name": "square(int)", "start_line": 2, "start_column": 12, "end_line": 4, "end_column": 1, "blocks": 2, "blocks_executed": 2, "execution_count": 3}
+      ],
+      "lines": [
+        {"line_number": 3, "count": 3, "unexecuted_block": false, "function_name": "_Z6squarei", "branches": []}
+      ]
+    }
+  ]
+}"#;
+
+    const OTHER_TU: &str = r#"{
+  "format_version": "1",
+  "gcc_version": "13.2.0",
+  "current_working_directory": "/project",
+  "data_file": "build/other.gcda",
+  "files": [
+    {
+      "file": "include/util.hpp",
+      "functions": [
+        {"name": "_Z6squarei", "demangled_name": "square(int)", "start_line": 2, "start_column": 12, "end_line": 4, "end_column": 1, "blocks": 2, "blocks_executed": 2, "execution_count": 2}
+      ],
+      "lines": [
+        {"line_number": 3, "count": 2, "unexecuted_block": false, "function_name": "_Z6squarei", "branches": []}
+      ]
+    }
+  ]
+}"#;
+
+    fn get_section<'a>(report: &'a LcovReport, source_file: &str) -> &'a SectionValue {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from(source_file),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn when_parsing_a_report_relative_files_shall_be_resolved_from_working_directory() {
+        let (report, _) = parse(MAIN_TU).unwrap();
+        let files = report
+            .sections
+            .keys()
+            .map(|key| key.source_file.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec!["/project/include/util.hpp", "/project/src/main.cpp"]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_report_lines_shall_have_their_hit_count() {
+        let (report, _) = parse(MAIN_TU).unwrap();
+        let lines = get_section(&report, "/project/src/main.cpp")
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(3, 1), (4, 1), (5, 0)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_branches_of_unexecuted_lines_shall_not_be_tested() {
+        let (report, _) = parse(MAIN_TU).unwrap();
+        let branches = get_section(&report, "/project/src/main.cpp")
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.block, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            branches,
+            vec![(4, 0, 0, Some(1)), (4, 0, 1, Some(0)), (5, 0, 0, None)]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_report_functions_shall_use_demangled_names() {
+        let (report, _) = parse(MAIN_TU).unwrap();
+        let functions = get_section(&report, "/project/include/util.hpp")
+            .functions
+            .iter()
+            .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(functions, vec![("square(int)", Some(2), 3)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_function_end_lines_shall_be_kept() {
+        let (_, function_end_lines) = parse(MAIN_TU).unwrap();
+        assert_eq!(
+            function_end_lines.get(Path::new("/project/src/main.cpp")),
+            Some(&BTreeMap::from([("main".to_string(), 7)]))
+        );
+    }
+
+    #[test]
+    fn when_a_line_is_listed_for_several_instances_hits_shall_be_summed() {
+        let (report, _) = parse(
+            r#"{"gcc_version": "13.2.0", "files": [{"file": "/a.hpp", "functions": [], "lines": [
+                {"line_number": 2, "count": 1, "branches": [{"count": 1}]},
+                {"line_number": 2, "count": 2, "branches": [{"count": 2}]}
+            ]}]}"#,
+        )
+        .unwrap();
+        let section = get_section(&report, "/a.hpp");
+        assert_eq!(section.lines.get(&LineKey { line: 2 }).unwrap().count, 3);
+        assert_eq!(
+            section
+                .branches
+                .iter()
+                .map(|(key, value)| (key.block, value.taken))
+                .collect::<Vec<_>>(),
+            vec![(0, Some(1)), (1, Some(2))]
+        );
+    }
+
+    #[test]
+    fn when_parsing_several_translation_units_shared_headers_shall_be_merged() {
+        let (report, _) = parse_all([MAIN_TU, OTHER_TU].into_iter()).unwrap();
+        assert_eq!(report.sections.len(), 2);
+
+        let header = get_section(&report, "/project/include/util.hpp");
+        assert_eq!(header.lines.get(&LineKey { line: 3 }).unwrap().count, 5);
+        assert_eq!(
+            header
+                .functions
+                .get(&FunctionKey {
+                    name: "square(int)".into()
+                })
+                .unwrap()
+                .count,
+            5
+        );
+    }
+
+    #[test]
+    fn when_parsing_an_invalid_report_it_shall_fail() {
+        assert!(parse(r#"{"files": [{"lines": []}]}"#)
+            .unwrap_err()
+            .starts_with("Invalid gcov JSON report: "));
+    }
+
+    #[test]
+    fn gcov_json_files_shall_be_recognized_by_their_name() {
+        assert!(is_gcov_json_file(Path::new("build/main.gcov.json.gz")));
+        assert!(is_gcov_json_file(Path::new("main.gcov.json")));
+        assert!(!is_gcov_json_file(Path::new("main.json")));
+        assert!(!is_gcov_json_file(Path::new("lcov.info")));
+    }
+}
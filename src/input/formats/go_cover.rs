@@ -0,0 +1,178 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use lcov::report::section::{line::Key as LineKey, Key as SectionKey};
+use lcov::Report as LcovReport;
+
+pub const MODE_PREFIX: &str = "mode:";
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Block {
+    file: PathBuf,
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+/// Parse a Go coverage profile (`go test -coverprofile`) into an LCOV report.
+/// Go counts executions per block of statements, each block is projected on
+/// the lines it spans.
+pub fn parse(content: &str) -> Result<LcovReport, String> {
+    parse_profile(content).map_err(|e| format!("Invalid Go coverage profile: {}", e))
+}
+
+fn parse_profile(content: &str) -> Result<LcovReport, String> {
+    let mut lines = content.lines().enumerate();
+    let mode = lines
+        .next()
+        .and_then(|(_, line)| line.trim().strip_prefix(MODE_PREFIX))
+        .map(|mode| mode.trim())
+        .ok_or("missing mode line")?;
+    let sum_counts = match mode {
+        "set" => false,
+        "count" | "atomic" => true,
+        _ => return Err(format!("unknown mode '{}'", mode)),
+    };
+
+    // When tests of several packages cover the same package, its blocks are
+    // listed several times
+    let mut blocks = BTreeMap::<Block, u64>::new();
+    for (index, line) in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(MODE_PREFIX) {
+            continue;
+        }
+        let (block, count) = parse_block(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        let block_count = blocks.entry(block).or_default();
+        *block_count = match sum_counts {
+            true => block_count.saturating_add(count),
+            false => (*block_count).max(count),
+        };
+    }
+
+    let mut report = LcovReport::new();
+    for (block, count) in blocks {
+        let section = report
+            .sections
+            .entry(SectionKey {
+                test_name: String::new(),
+                source_file: block.file,
+            })
+            .or_default();
+        for line in block.start_line..=block.end_line {
+            let value = section.lines.entry(LineKey { line }).or_default();
+            value.count = value.count.max(count);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Parse a block line, like `example.com/pkg/file.go:10.13,12.2 2 1`
+fn parse_block(line: &str) -> Result<(Block, u64), String> {
+    let (file, range_and_counts) = line.rsplit_once(':').ok_or("missing file name")?;
+    let mut parts = range_and_counts.split_whitespace();
+    let (start, end) = parts
+        .next()
+        .and_then(|range| range.split_once(','))
+        .ok_or("missing block range")?;
+    let (start_line, start_column) = parse_position(start)?;
+    let (end_line, end_column) = parse_position(end)?;
+    let _statements = parse_number::<u32>(parts.next(), "number of statements")?;
+    let count = parse_number::<u64>(parts.next(), "count")?;
+
+    Ok((
+        Block {
+            file: PathBuf::from(file),
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        },
+        count,
+    ))
+}
+
+fn parse_position(position: &str) -> Result<(u32, u32), String> {
+    let (line, column) = position
+        .split_once('.')
+        .ok_or_else(|| format!("invalid position '{}'", position))?;
+    Ok((
+        parse_number(Some(line), "line")?,
+        parse_number(Some(column), "column")?,
+    ))
+}
+
+fn parse_number<T: std::str::FromStr>(value: Option<&str>, name: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing {}", name))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid {} '{}'", name, value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PROFILE: &str = "mode: count
+example.com/shop/cart/cart.go:10.30,12.16 2 3
+example.com/shop/cart/cart.go:12.16,14.3 1 0
+example.com/shop/cart/cart.go:15.2,15.15 1 3
+example.com/shop/cart/total.go:3.20,5.2 1 0
+";
+
+    fn get_lines(report: &LcovReport, file: &str) -> Vec<(u32, u64)> {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from(file),
+            })
+            .unwrap()
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect()
+    }
+
+    #[test]
+    fn when_parsing_a_profile_blocks_shall_be_projected_on_lines() {
+        let report = parse(PROFILE).unwrap();
+        assert_eq!(
+            get_lines(&report, "example.com/shop/cart/cart.go"),
+            vec![(10, 3), (11, 3), (12, 3), (13, 0), (14, 0), (15, 3)]
+        );
+        assert_eq!(
+            get_lines(&report, "example.com/shop/cart/total.go"),
+            vec![(3, 0), (4, 0), (5, 0)]
+        );
+    }
+
+    #[test]
+    fn when_a_block_is_listed_twice_in_count_mode_counts_shall_be_summed() {
+        let report = parse("mode: atomic\na.go:1.1,1.10 1 2\na.go:1.1,1.10 1 3\n").unwrap();
+        assert_eq!(get_lines(&report, "a.go"), vec![(1, 5)]);
+    }
+
+    #[test]
+    fn when_a_block_is_listed_twice_in_set_mode_it_shall_be_covered_once() {
+        let report = parse("mode: set\na.go:1.1,1.10 1 1\na.go:1.1,1.10 1 1\n").unwrap();
+        assert_eq!(get_lines(&report, "a.go"), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn when_parsing_a_profile_without_mode_it_shall_fail() {
+        assert_eq!(
+            parse("a.go:1.1,1.10 1 1\n").unwrap_err(),
+            "Invalid Go coverage profile: missing mode line"
+        );
+    }
+
+    #[test]
+    fn when_parsing_an_invalid_block_it_shall_fail_with_its_line_number() {
+        assert_eq!(
+            parse("mode: set\na.go:1.1,1.10 1 1\na.go:2.1,x.10 1 1\n").unwrap_err(),
+            "Invalid Go coverage profile: line 3: invalid line 'x'"
+        );
+    }
+}
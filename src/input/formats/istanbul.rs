@@ -0,0 +1,224 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use lcov::report::section::{
+    branch::{Key as BranchKey, Value as BranchValue},
+    function::{Key as FunctionKey, Value as FunctionValue},
+    line::Key as LineKey,
+    Key as SectionKey, Value as SectionValue,
+};
+use lcov::Report as LcovReport;
+use serde::Deserialize;
+
+pub const STATEMENT_MAP_KEY: &str = "\"statementMap\"";
+
+#[derive(Deserialize)]
+struct FileCoverage {
+    path: Option<PathBuf>,
+    #[serde(rename = "statementMap")]
+    statement_map: HashMap<String, Location>,
+    #[serde(rename = "fnMap", default)]
+    fn_map: HashMap<String, FunctionMapping>,
+    #[serde(rename = "branchMap", default)]
+    branch_map: HashMap<String, BranchMapping>,
+    s: HashMap<String, u64>,
+    #[serde(default)]
+    f: HashMap<String, u64>,
+    #[serde(default)]
+    b: HashMap<String, Vec<u64>>,
+}
+
+#[derive(Deserialize)]
+struct Location {
+    start: Position,
+}
+
+#[derive(Deserialize)]
+struct Position {
+    line: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FunctionMapping {
+    name: String,
+    decl: Option<Location>,
+    line: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct BranchMapping {
+    loc: Option<Location>,
+    line: Option<u32>,
+}
+
+/// Parse an Istanbul (nyc, jest, c8...) `coverage-final.json` report into an
+/// LCOV report, projecting statements, functions and branches the same way
+/// Istanbul's own LCOV reporter does.
+pub fn parse(content: &str) -> Result<LcovReport, String> {
+    parse_report(content).map_err(|e| format!("Invalid Istanbul report: {}", e))
+}
+
+fn parse_report(content: &str) -> Result<LcovReport, String> {
+    let files = serde_json::from_str::<BTreeMap<String, FileCoverage>>(content)
+        .map_err(|e| e.to_string())?;
+
+    let mut report = LcovReport::new();
+    for (file_name, file) in files {
+        let key = SectionKey {
+            test_name: String::new(),
+            source_file: file.path.clone().unwrap_or_else(|| file_name.into()),
+        };
+        let section = report.sections.entry(key).or_default();
+        add_lines(section, &file);
+        add_functions(section, &file);
+        add_branches(section, &file)?;
+    }
+
+    Ok(report)
+}
+
+/// A line is executed as many times as the most executed statement starting
+/// on it
+fn add_lines(section: &mut SectionValue, file: &FileCoverage) {
+    for (id, location) in file.statement_map.iter() {
+        let Some(line) = location.start.line else {
+            continue;
+        };
+        let count = file.s.get(id).copied().unwrap_or(0);
+        let value = section.lines.entry(LineKey { line }).or_default();
+        value.count = value.count.max(count);
+    }
+}
+
+fn add_functions(section: &mut SectionValue, file: &FileCoverage) {
+    for (id, function) in file.fn_map.iter() {
+        let start_line = function
+            .decl
+            .as_ref()
+            .and_then(|decl| decl.start.line)
+            .or(function.line);
+        let value = section
+            .functions
+            .entry(FunctionKey {
+                name: function.name.clone(),
+            })
+            .or_insert(FunctionValue {
+                start_line,
+                count: 0,
+            });
+        value.count = value
+            .count
+            .saturating_add(file.f.get(id).copied().unwrap_or(0));
+    }
+}
+
+fn add_branches(section: &mut SectionValue, file: &FileCoverage) -> Result<(), String> {
+    for (id, branch) in file.branch_map.iter() {
+        let line = branch
+            .loc
+            .as_ref()
+            .and_then(|loc| loc.start.line)
+            .or(branch.line)
+            .ok_or_else(|| format!("branch {} without line", id))?;
+        let block = id
+            .parse::<u32>()
+            .map_err(|_| format!("invalid branch id '{}'", id))?;
+        let counts = file.b.get(id).map(Vec::as_slice).unwrap_or_default();
+
+        for (index, taken) in counts.iter().enumerate() {
+            section.branches.insert(
+                BranchKey {
+                    line,
+                    block,
+                    branch: index as u32,
+                },
+                BranchValue {
+                    taken: Some(*taken),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const REPORT: &str = r#"{
+  "/project/src/cart.js": {
+    "path": "/project/src/cart.js",
+    "statementMap": {
+      "0": {"start": {"line": 1, "column": 0}, "end": {"line": 1, "column": 30}},
+      "1": {"start": {"line": 3, "column": 2}, "end": {"line": 3, "column": 20}},
+      "2": {"start": {"line": 3, "column": 21}, "end": {"line": 3, "column": 40}},
+      "3": {"start": {"line": 5, "column": 4}, "end": {"line": 5, "column": 16}}
+    },
+    "fnMap": {
+      "0": {"name": "total", "decl": {"start": {"line": 2, "column": 9}, "end": {"line": 2, "column": 14}}, "loc": {"start": {"line": 2, "column": 0}, "end": {"line": 7, "column": 1}}, "line": 2},
+      "1": {"name": "(anonymous_1)", "decl": {"start": {"line": 9, "column": 0}, "end": {"line": 9, "column": 5}}, "loc": {"start": {"line": 9, "column": 0}, "end": {"line": 9, "column": 20}}, "line": 9}
+    },
+    "branchMap": {
+      "0": {"loc": {"start": {"line": 4, "column": 2}, "end": {"line": 6, "column": 3}}, "type": "if", "locations": [{"start": {"line": 4, "column": 2}, "end": {"line": 6, "column": 3}}, {"start": {}, "end": {}}], "line": 4}
+    },
+    "s": {"0": 1, "1": 2, "2": 0, "3": 0},
+    "f": {"0": 2, "1": 0},
+    "b": {"0": [0, 2]}
+  }
+}"#;
+
+    fn get_cart(report: &LcovReport) -> &SectionValue {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from("/project/src/cart.js"),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn when_parsing_a_report_lines_shall_have_the_count_of_their_most_executed_statement() {
+        let report = parse(REPORT).unwrap();
+        let lines = get_cart(&report)
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(1, 1), (3, 2), (5, 0)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_functions_shall_start_at_their_declaration() {
+        let report = parse(REPORT).unwrap();
+        let functions = get_cart(&report)
+            .functions
+            .iter()
+            .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            vec![("(anonymous_1)", Some(9), 0), ("total", Some(2), 2)]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_report_each_branch_location_shall_be_a_branch() {
+        let report = parse(REPORT).unwrap();
+        let branches = get_cart(&report)
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.block, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(branches, vec![(4, 0, 0, Some(0)), (4, 0, 1, Some(2))]);
+    }
+
+    #[test]
+    fn when_parsing_an_invalid_report_it_shall_fail() {
+        assert!(parse(r#"{"/a.js": {"s": {}}}"#)
+            .unwrap_err()
+            .starts_with("Invalid Istanbul report: "));
+    }
+}
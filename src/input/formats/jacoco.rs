@@ -0,0 +1,262 @@
+use std::path::PathBuf;
+
+use lcov::report::section::{
+    branch::{Key as BranchKey, Value as BranchValue},
+    function::{Key as FunctionKey, Value as FunctionValue},
+    line::{Key as LineKey, Value as LineValue},
+    Key as SectionKey, Value as SectionValue,
+};
+use lcov::Report as LcovReport;
+use roxmltree::Node;
+
+use super::xml::{children, parse_attribute, parse_document};
+
+/// Parse a JaCoCo XML report into the same structure as an LCOV report, one
+/// section per source file (package path joined with the source file name).
+///
+/// JaCoCo does not record hit counts: a line is counted once when at least one
+/// of its instructions was covered, and a method once when it was entered.
+pub fn parse(content: &str) -> Result<LcovReport, String> {
+    parse_report(content).map_err(|e| format!("Invalid JaCoCo report: {}", e))
+}
+
+fn parse_report(content: &str) -> Result<LcovReport, String> {
+    let document = parse_document(content)?;
+
+    let root = document.root_element();
+    if !root.has_tag_name("report") {
+        return Err(format!(
+            "expected a <report> root element, found <{}>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut report = LcovReport::new();
+    for package in root
+        .descendants()
+        .filter(|node| node.has_tag_name("package"))
+    {
+        add_package(&mut report, package)?;
+    }
+
+    Ok(report)
+}
+
+fn get_section<'a>(
+    report: &'a mut LcovReport,
+    package_name: &str,
+    source_file_name: &str,
+) -> &'a mut SectionValue {
+    let key = SectionKey {
+        test_name: String::new(),
+        source_file: PathBuf::from(package_name).join(source_file_name),
+    };
+    report.sections.entry(key).or_default()
+}
+
+fn add_package(report: &mut LcovReport, package: Node) -> Result<(), String> {
+    let package_name = package.attribute("name").ok_or("<package> without name")?;
+
+    for source_file in children(package, "sourcefile") {
+        let source_file_name = source_file
+            .attribute("name")
+            .ok_or("<sourcefile> without name")?;
+        let section = get_section(report, package_name, source_file_name);
+        for line in children(source_file, "line") {
+            add_line(section, line)?;
+        }
+    }
+
+    for class in children(package, "class") {
+        let Some(source_file_name) = class.attribute("sourcefilename") else {
+            continue;
+        };
+        let class_name = class
+            .attribute("name")
+            .ok_or("<class> without name")?
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        let section = get_section(report, package_name, source_file_name);
+        for method in children(class, "method") {
+            add_method(section, class_name, method)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_line(section: &mut SectionValue, line: Node) -> Result<(), String> {
+    let line_number: u32 = parse_attribute(line, "nr")?;
+    let covered_instructions: u32 = parse_attribute(line, "ci")?;
+    let missed_branches: u32 = parse_attribute(line, "mb")?;
+    let covered_branches: u32 = parse_attribute(line, "cb")?;
+
+    let is_covered = covered_instructions > 0;
+    section.lines.insert(
+        LineKey { line: line_number },
+        LineValue {
+            count: is_covered as u64,
+            ..Default::default()
+        },
+    );
+
+    for branch in 0..missed_branches + covered_branches {
+        let taken = match is_covered {
+            false => None,
+            true if branch < covered_branches => Some(1),
+            true => Some(0),
+        };
+        section.branches.insert(
+            BranchKey {
+                line: line_number,
+                block: 0,
+                branch,
+            },
+            BranchValue { taken },
+        );
+    }
+
+    Ok(())
+}
+
+fn add_method(section: &mut SectionValue, class_name: &str, method: Node) -> Result<(), String> {
+    let method_name = method.attribute("name").ok_or("<method> without name")?;
+    let start_line = method
+        .attribute("line")
+        .and_then(|line| line.parse::<u32>().ok());
+    let count = match children(method, "counter").find(|c| c.attribute("type") == Some("METHOD")) {
+        Some(counter) => parse_attribute::<u64>(counter, "covered")?,
+        None => 0,
+    };
+
+    // Overloaded methods share a name, the descriptor tells them apart
+    let mut name = format!("{}.{}", class_name, method_name);
+    let is_overload = section
+        .functions
+        .get(&FunctionKey { name: name.clone() })
+        .is_some_and(|existing| existing.start_line != start_line);
+    if is_overload {
+        name.push_str(method.attribute("desc").unwrap_or_default());
+    }
+
+    section
+        .functions
+        .insert(FunctionKey { name }, FunctionValue { start_line, count });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<!DOCTYPE report PUBLIC "-//JACOCO//DTD Report 1.1//EN" "report.dtd">
+<report name="app">
+    <sessioninfo id="session" start="1713000000000" dump="1713000001000"/>
+    <group name="core">
+        <package name="com/example/core">
+            <class name="com/example/core/Calculator" sourcefilename="Calculator.kt">
+                <method name="&lt;init&gt;" desc="()V" line="3">
+                    <counter type="METHOD" missed="0" covered="1"/>
+                </method>
+                <method name="divide" desc="(II)I" line="5">
+                    <counter type="METHOD" missed="0" covered="1"/>
+                </method>
+                <method name="divide" desc="(DD)D" line="10">
+                    <counter type="METHOD" missed="1" covered="0"/>
+                </method>
+            </class>
+            <sourcefile name="Calculator.kt">
+                <line nr="3" mi="0" ci="3" mb="0" cb="0"/>
+                <line nr="6" mi="0" ci="4" mb="1" cb="1"/>
+                <line nr="11" mi="2" ci="0" mb="2" cb="0"/>
+                <counter type="LINE" missed="1" covered="2"/>
+            </sourcefile>
+        </package>
+    </group>
+</report>"#;
+
+    fn get_calculator(report: &LcovReport) -> &SectionValue {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from("com/example/core/Calculator.kt"),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn when_parsing_a_report_source_files_shall_be_grouped_by_package_path() {
+        let report = parse(REPORT).unwrap();
+        assert_eq!(report.sections.len(), 1);
+        get_calculator(&report);
+    }
+
+    #[test]
+    fn when_parsing_a_report_lines_with_covered_instructions_shall_be_covered() {
+        let report = parse(REPORT).unwrap();
+        let lines = get_calculator(&report)
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec![(3, 1), (6, 1), (11, 0)]);
+    }
+
+    #[test]
+    fn when_parsing_a_report_branch_counters_shall_become_branches() {
+        let report = parse(REPORT).unwrap();
+        let branches = get_calculator(&report)
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            branches,
+            vec![
+                (6, 0, Some(1)),
+                (6, 1, Some(0)),
+                (11, 0, None),
+                (11, 1, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_report_methods_shall_be_named_after_their_class() {
+        let report = parse(REPORT).unwrap();
+        let functions = get_calculator(&report)
+            .functions
+            .iter()
+            .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            vec![
+                ("Calculator.<init>", Some(3), 1),
+                ("Calculator.divide", Some(5), 1),
+                ("Calculator.divide(DD)D", Some(10), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn when_parsing_a_non_jacoco_document_it_shall_fail() {
+        assert_eq!(
+            parse("<coverage></coverage>").unwrap_err(),
+            "Invalid JaCoCo report: expected a <report> root element, found <coverage>"
+        );
+    }
+
+    #[test]
+    fn when_a_line_has_no_number_it_shall_fail() {
+        assert_eq!(
+            parse(r#"<report><package name="p"><sourcefile name="A.java"><line mi="0" ci="1" mb="0" cb="0"/></sourcefile></package></report>"#)
+                .unwrap_err(),
+            "Invalid JaCoCo report: <line> without nr"
+        );
+    }
+}
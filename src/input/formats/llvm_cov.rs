@@ -0,0 +1,356 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use lcov::report::section::{
+    branch::{Key as BranchKey, Value as BranchValue},
+    function::{Key as FunctionKey, Value as FunctionValue},
+    line::Key as LineKey,
+    Key as SectionKey, Value as SectionValue,
+};
+use lcov::Report as LcovReport;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::core::AggregatedCoverageCounters;
+
+pub const EXPORT_TYPE: &str = "llvm.coverage.json.export";
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(rename = "type")]
+    export_type: String,
+    data: Vec<ExportData>,
+}
+
+#[derive(Deserialize)]
+struct ExportData {
+    #[serde(default)]
+    files: Vec<File>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    filename: PathBuf,
+    #[serde(default)]
+    segments: Vec<Vec<JsonValue>>,
+    #[serde(default)]
+    branches: Vec<Vec<JsonValue>>,
+    summary: Summary,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    regions: SummaryCounters,
+}
+
+#[derive(Deserialize)]
+struct SummaryCounters {
+    count: u32,
+    covered: u32,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    name: String,
+    count: u64,
+    #[serde(default)]
+    regions: Vec<Vec<JsonValue>>,
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+}
+
+struct Segment {
+    line: u32,
+    count: u64,
+    has_count: bool,
+    is_region_entry: bool,
+    is_gap_region: bool,
+}
+impl Segment {
+    fn from_json(values: &[JsonValue]) -> Result<Self, String> {
+        let get_u64 = |i: usize| values.get(i).and_then(|v| v.as_u64());
+        let get_bool = |i: usize| values.get(i).and_then(|v| v.as_bool());
+        Ok(Segment {
+            line: get_u64(0).ok_or("invalid segment line")? as u32,
+            count: get_u64(2).ok_or("invalid segment count")?,
+            has_count: get_bool(3).ok_or("invalid segment hasCount")?,
+            is_region_entry: get_bool(4).ok_or("invalid segment isRegionEntry")?,
+            is_gap_region: get_bool(5).unwrap_or(false),
+        })
+    }
+
+    fn is_start_of_region(&self) -> bool {
+        !self.is_gap_region && self.has_count && self.is_region_entry
+    }
+}
+
+/// Parse the JSON export of llvm-cov (`llvm-cov export -format=text` or
+/// `cargo llvm-cov --json`) into an LCOV report, along with the region
+/// coverage of each file, which LCOV has no room for.
+pub fn parse(
+    content: &str,
+) -> Result<(LcovReport, BTreeMap<PathBuf, AggregatedCoverageCounters>), String> {
+    parse_export(content).map_err(|e| format!("Invalid llvm-cov export: {}", e))
+}
+
+fn parse_export(
+    content: &str,
+) -> Result<(LcovReport, BTreeMap<PathBuf, AggregatedCoverageCounters>), String> {
+    let export = serde_json::from_str::<Export>(content).map_err(|e| e.to_string())?;
+    if export.export_type != EXPORT_TYPE {
+        return Err(format!("unexpected export type '{}'", export.export_type));
+    }
+
+    let mut report = LcovReport::new();
+    let mut regions = BTreeMap::<PathBuf, AggregatedCoverageCounters>::new();
+
+    for data in export.data {
+        for file in data.files {
+            let file_regions = regions.entry(file.filename.clone()).or_default();
+            file_regions.count += file.summary.regions.count;
+            file_regions.covered_count += file.summary.regions.covered;
+
+            let section = get_section(&mut report, file.filename);
+            add_lines(section, &file.segments)?;
+            add_branches(section, &file.branches)?;
+        }
+
+        for function in data.functions {
+            add_function(&mut report, function)?;
+        }
+    }
+
+    Ok((report, regions))
+}
+
+fn get_section(report: &mut LcovReport, source_file: PathBuf) -> &mut SectionValue {
+    let key = SectionKey {
+        test_name: String::new(),
+        source_file,
+    };
+    report.sections.entry(key).or_default()
+}
+
+/// Project the segments of a file onto its lines, the same way llvm-cov does
+/// when it renders line coverage: a line is executed as many times as the
+/// most executed region starting on it, or as the region wrapping it.
+fn add_lines(section: &mut SectionValue, segments: &[Vec<JsonValue>]) -> Result<(), String> {
+    let segments = segments
+        .iter()
+        .map(|segment| Segment::from_json(segment))
+        .collect::<Result<Vec<Segment>, String>>()?;
+
+    let Some(first_segment) = segments.first() else {
+        return Ok(());
+    };
+
+    let mut line = first_segment.line;
+    let mut next = 0;
+    let mut wrapped: Option<&Segment> = None;
+    let mut line_segments: Vec<&Segment> = vec![];
+
+    while next < segments.len() {
+        if let Some(last) = line_segments.last() {
+            wrapped = Some(last);
+        }
+        line_segments.clear();
+        while next < segments.len() && segments[next].line == line {
+            line_segments.push(&segments[next]);
+            next += 1;
+        }
+
+        if let Some(count) = get_line_count(&line_segments, wrapped) {
+            let value = section.lines.entry(LineKey { line }).or_default();
+            value.count = value.count.max(count);
+        }
+        line += 1;
+    }
+
+    Ok(())
+}
+
+fn get_line_count(line_segments: &[&Segment], wrapped: Option<&Segment>) -> Option<u64> {
+    let starts_skipped_region = line_segments
+        .first()
+        .is_some_and(|segment| !segment.has_count && segment.is_region_entry);
+    let starts_region = line_segments.iter().any(|s| s.is_start_of_region());
+    let is_mapped = !starts_skipped_region
+        && (wrapped.is_some_and(|segment| segment.has_count) || starts_region)
+        || line_segments
+            .iter()
+            .any(|segment| segment.is_region_entry && segment.has_count);
+
+    if !is_mapped {
+        return None;
+    }
+
+    let wrapped_count = wrapped.map_or(0, |segment| segment.count);
+    Some(
+        line_segments
+            .iter()
+            .filter(|segment| segment.is_start_of_region())
+            .map(|segment| segment.count)
+            .fold(wrapped_count, u64::max),
+    )
+}
+
+fn add_branches(section: &mut SectionValue, branches: &[Vec<JsonValue>]) -> Result<(), String> {
+    for (block, branch) in branches.iter().enumerate() {
+        let get_u64 = |i: usize| branch.get(i).and_then(|v| v.as_u64());
+        let line = get_u64(0).ok_or("invalid branch line")? as u32;
+        let true_count = get_u64(4).ok_or("invalid branch true count")?;
+        let false_count = get_u64(5).ok_or("invalid branch false count")?;
+
+        for (index, taken) in [true_count, false_count].into_iter().enumerate() {
+            section.branches.insert(
+                BranchKey {
+                    line,
+                    block: block as u32,
+                    branch: index as u32,
+                },
+                BranchValue { taken: Some(taken) },
+            );
+        }
+    }
+    Ok(())
+}
+
+fn add_function(report: &mut LcovReport, function: Function) -> Result<(), String> {
+    let Some(file_name) = function.filenames.into_iter().next() else {
+        return Ok(());
+    };
+    let start_line = function
+        .regions
+        .first()
+        .and_then(|region| region.first())
+        .and_then(|line| line.as_u64())
+        .map(|line| line as u32);
+
+    let section = get_section(report, file_name);
+    let value = section
+        .functions
+        .entry(FunctionKey {
+            name: function.name,
+        })
+        .or_insert(FunctionValue {
+            start_line,
+            count: 0,
+        });
+    value.count = value.count.saturating_add(function.count);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+  "type": "llvm.coverage.json.export",
+  "version": "2.0.1",
+  "data": [{
+    "files": [{
+      "filename": "/project/src/lib.rs",
+      "segments": [
+        [1, 1, 4, true, true, false],
+        [3, 5, 0, true, true, false],
+        [4, 6, 4, true, false, false],
+        [6, 2, 0, false, false, false],
+        [8, 1, 0, true, true, false],
+        [9, 2, 0, false, false, false]
+      ],
+      "branches": [[3, 8, 3, 14, 4, 0, 0, 0, 4]],
+      "expansions": [],
+      "summary": {
+        "lines": {"count": 8, "covered": 5, "percent": 62.5},
+        "functions": {"count": 2, "covered": 1, "percent": 50},
+        "regions": {"count": 3, "covered": 1, "notcovered": 2, "percent": 33.33},
+        "branches": {"count": 2, "covered": 1, "notcovered": 1, "percent": 50}
+      }
+    }],
+    "functions": [
+      {"name": "_RNvCs1_3lib3add", "count": 4, "regions": [[1, 1, 6, 2, 4, 0, 0, 0]], "branches": [], "filenames": ["/project/src/lib.rs"]},
+      {"name": "_RNvCs1_3lib6unused", "count": 0, "regions": [[8, 1, 9, 2, 0, 0, 0, 0]], "branches": [], "filenames": ["/project/src/lib.rs"]}
+    ],
+    "totals": {}
+  }]
+}"#;
+
+    fn get_lib(report: &LcovReport) -> &SectionValue {
+        report
+            .sections
+            .get(&SectionKey {
+                test_name: String::new(),
+                source_file: PathBuf::from("/project/src/lib.rs"),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn when_parsing_an_export_segments_shall_be_projected_on_lines() {
+        let (report, _) = parse(EXPORT).unwrap();
+        let lines = get_lib(&report)
+            .lines
+            .iter()
+            .map(|(key, value)| (key.line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                (1, 4),
+                (2, 4),
+                (3, 4),
+                (4, 0),
+                (5, 4),
+                (6, 4),
+                (8, 0),
+                (9, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn when_parsing_an_export_functions_shall_start_at_their_first_region() {
+        let (report, _) = parse(EXPORT).unwrap();
+        let functions = get_lib(&report)
+            .functions
+            .iter()
+            .map(|(key, value)| (key.name.as_str(), value.start_line, value.count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            vec![
+                ("_RNvCs1_3lib3add", Some(1), 4),
+                ("_RNvCs1_3lib6unused", Some(8), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn when_parsing_an_export_each_branch_shall_have_a_true_and_false_outcome() {
+        let (report, _) = parse(EXPORT).unwrap();
+        let branches = get_lib(&report)
+            .branches
+            .iter()
+            .map(|(key, value)| (key.line, key.block, key.branch, value.taken))
+            .collect::<Vec<_>>();
+        assert_eq!(branches, vec![(3, 0, 0, Some(4)), (3, 0, 1, Some(0))]);
+    }
+
+    #[test]
+    fn when_parsing_an_export_regions_shall_come_from_file_summary() {
+        let (_, regions) = parse(EXPORT).unwrap();
+        assert_eq!(
+            regions.get(&PathBuf::from("/project/src/lib.rs")),
+            Some(&AggregatedCoverageCounters::new(3, 1))
+        );
+    }
+
+    #[test]
+    fn when_parsing_another_json_document_it_shall_fail() {
+        assert_eq!(
+            parse(r#"{"type": "something.else", "data": []}"#).unwrap_err(),
+            "Invalid llvm-cov export: unexpected export type 'something.else'"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use roxmltree::{Document, Node, ParsingOptions};
+
+/// Parse an XML document, accepting the DOCTYPE declarations that coverage
+/// tools usually put in their reports
+pub fn parse_document(content: &str) -> Result<Document<'_>, String> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    Document::parse_with_options(content, options).map_err(|e| e.to_string())
+}
+
+/// Name of the root element of an XML document, without parsing it entirely
+pub fn get_root_element_name(content: &str) -> Option<&str> {
+    let mut rest = content;
+    loop {
+        let start = rest.find('<')?;
+        rest = &rest[start + 1..];
+        if rest.starts_with('?') || rest.starts_with('!') {
+            continue;
+        }
+        let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+        return Some(&rest[..end]);
+    }
+}
+
+pub fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    tag_name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(tag_name))
+}
+
+pub fn parse_attribute<T: std::str::FromStr>(node: Node, name: &str) -> Result<T, String> {
+    let value =
+        node.attribute(name)
+            .ok_or(format!("<{}> without {}", node.tag_name().name(), name))?;
+    value.trim().parse::<T>().map_err(|_| {
+        format!(
+            "invalid {} '{}' in <{}>",
+            name,
+            value,
+            node.tag_name().name()
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn root_element_name_shall_skip_declaration_doctype_and_comments() {
+        assert_eq!(
+            get_root_element_name(
+                r#"<?xml version="1.0"?><!DOCTYPE report PUBLIC "-//JACOCO//DTD Report 1.1//EN" "report.dtd"><!-- generated --><report name="x"/>"#
+            ),
+            Some("report")
+        );
+    }
+
+    #[test]
+    fn root_element_name_of_non_xml_content_shall_be_none() {
+        assert_eq!(get_root_element_name("TN:\nSF:main.cpp\n"), None);
+    }
+
+    #[test]
+    fn when_attribute_is_missing_parse_attribute_shall_fail() {
+        let document = parse_document(r#"<line number="1"/>"#).unwrap();
+        assert_eq!(
+            parse_attribute::<u32>(document.root_element(), "hits").unwrap_err(),
+            "<line> without hits"
+        );
+    }
+
+    #[test]
+    fn when_attribute_is_invalid_parse_attribute_shall_fail() {
+        let document = parse_document(r#"<line number="one"/>"#).unwrap();
+        assert_eq!(
+            parse_attribute::<u32>(document.root_element(), "number").unwrap_err(),
+            "invalid number 'one' in <line>"
+        );
+    }
+}
@@ -7,10 +7,15 @@ mod html {
 
 mod aggregation {
     mod aggregated;
+    pub(crate) mod baseline;
+    mod demangle;
+    pub(crate) mod exclusions;
     pub(crate) mod multi_report;
+    pub(crate) mod ratchet;
     pub(crate) mod tested_file;
     mod tested_module;
     pub(crate) mod tested_root;
+    pub(crate) mod thresholds;
     mod with_path;
 
     #[cfg(test)]
@@ -32,6 +37,7 @@ pub mod adapters {
             pub(crate) mod function;
             pub(crate) mod gauges;
             pub(crate) mod navigation;
+            pub(crate) mod test_filter;
         }
 
         #[cfg(test)]
@@ -52,7 +58,22 @@ pub mod adapters {
 
 mod input {
     pub(crate) mod aggregator_input;
+    pub(crate) mod compression;
     pub(crate) mod config;
+    pub(crate) mod diff;
+    pub(crate) mod discovery;
+    pub(crate) mod errors;
+    pub(crate) mod filters;
+
+    pub(crate) mod formats {
+        pub(crate) mod cobertura;
+        pub(crate) mod gcov_json;
+        pub(crate) mod go_cover;
+        pub(crate) mod istanbul;
+        pub(crate) mod jacoco;
+        pub(crate) mod llvm_cov;
+        pub(crate) mod xml;
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,7 @@
 use lcoviz::operations::run;
-use std::env::args;
+use std::{env::args, process::ExitCode};
 
-fn main() {
+fn main() -> ExitCode {
     let args = args().skip(1).collect::<Vec<String>>();
     run(args)
 }
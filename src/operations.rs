@@ -2,7 +2,7 @@ use crate::{
     adapters::{
         cli::{
             cli_output::{CliOutput, Console},
-            parser::{CliCommand, CliConfigParser},
+            parser::{CliCommand, CliConfigParser, RatchetOptions},
         },
         exporters::{mpa::MpaExporter, mpa_links::MpaLinksComputer, spa::SpaExporter},
         renderers::{
@@ -10,62 +10,374 @@ use crate::{
             text_single_page_renderer::TextSinglePageRenderer,
         },
     },
-    aggregation::{multi_report::MultiReport, tested_root::TestedRoot},
-    core::{Exporter, LocalFileSystem},
+    aggregation::{
+        baseline::Baseline,
+        multi_report::MultiReport,
+        ratchet::Ratchet,
+        tested_root::{BuildContext, TestedRoot},
+        thresholds::{ThresholdRules, Thresholds, ThresholdsCheck},
+    },
+    core::{AggregatedCoverage, Exporter, FileSystem, LocalFileSystem, TestedContainer},
+    file_provider::GitRevision,
     input::{
         aggregator_input::AggregatorInput,
-        config::{Config, Reporter},
+        config::{Config, Input, Reporter},
+        diff::ChangedLines,
+        discovery::expand_inputs,
+        errors::InputError,
+        filters::RemovedFiles,
     },
 };
-use std::path::PathBuf;
-
-fn build_multi_report_root(config: Config) -> Result<MultiReport, String> {
-    let mut multi_report = MultiReport::new(&config.name);
-    for input in AggregatorInput::build_from_inputs(config.inputs, &LocalFileSystem) {
-        multi_report.add_report(TestedRoot::new(input));
-    }
-    Ok(multi_report)
-}
-
-fn build_single_report_root(config: Config) -> Result<TestedRoot, String> {
-    let input = config.inputs.into_iter().next().unwrap();
-    let aggregator_input =
-        AggregatorInput::from_config_input(input, &LocalFileSystem).with_name(&config.name);
-    let tested_root = TestedRoot::new(aggregator_input);
-    Ok(tested_root)
-}
-
-macro_rules! export {
-    ($exporter_struct: ident, $renderer: expr, $config: expr) => {{
-        let output = $config.output.clone();
-        if $config.inputs.len() != 1 {
-            let multi_report = build_multi_report_root($config)?;
-            $exporter_struct::new($renderer, multi_report, &output, &LocalFileSystem).render_root();
-            Ok::<(), String>(())
-        } else {
-            let root = build_single_report_root($config)?;
-            $exporter_struct::new($renderer, root, &output, &LocalFileSystem).render_root();
-            Ok::<(), String>(())
-        }
-    }};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+};
+
+/// Exit status when the coverage does not reach the thresholds
+const THRESHOLDS_FAILURE: u8 = 2;
+
+/// Print the errors of the inputs that were skipped with `keep_going`
+fn print_skipped_inputs(skipped_inputs: Vec<InputError>, cli_output: &CliOutput<Console>) {
+    for error in skipped_inputs {
+        cli_output.print_warning(&format!("{}, skipping it", error));
+    }
 }
 
-fn run_report(config: Config, cli_output: &CliOutput<Console>) -> Result<(), String> {
-    let output = config.output.clone();
+/// Load each group of inputs into a single one, skipping the ones failing to
+/// load when the configuration allows it
+fn load_inputs(
+    input_groups: Vec<Vec<Input>>,
+    config: &Config,
+    cli_output: &CliOutput<Console>,
+) -> Result<Vec<AggregatorInput>, String> {
+    let (inputs, skipped_inputs) =
+        AggregatorInput::build_from_inputs(input_groups, &LocalFileSystem, config.keep_going)
+            .map_err(|e| e.to_string())?;
+    print_skipped_inputs(skipped_inputs, cli_output);
+    if !config.inputs.is_empty() && inputs.is_empty() {
+        return Err("No input could be loaded".to_string());
+    }
+    Ok(inputs)
+}
 
-    cli_output.print_introduction(&config);
+/// What the roots are built with, besides their inputs
+fn load_build_context(config: &Config) -> Result<BuildContext, String> {
+    let context = BuildContext {
+        source_rev: open_source_rev(config)?,
+        exclusion_markers: config.get_exclusion_markers().cloned(),
+        changed_lines: load_changed_lines(config)?,
+        baseline: None,
+    };
+    Ok(BuildContext {
+        baseline: load_baseline(config, &context)?,
+        ..context
+    })
+}
 
-    match config.reporter {
-        Reporter::MpaHtmlLightReporter => export!(
-            MpaExporter,
-            HtmlLightRenderer::new(MpaLinksComputer),
-            config
-        )?,
-        Reporter::TextSummaryReporter => export!(SpaExporter, TextSinglePageRenderer, config)?,
+/// Lines changed by the diff whose coverage is reported, if any
+fn load_changed_lines(config: &Config) -> Result<Option<ChangedLines>, String> {
+    let changed_lines = match (&config.diff, &config.diff_base) {
+        (Some(_), Some(_)) => return Err("Only one of diff and diff base may be given".to_string()),
+        (Some(diff), None) => {
+            let diff = LocalFileSystem
+                .read(diff)
+                .map_err(|e| format!("Could not read diff {}: {}", diff.display(), e))?;
+            ChangedLines::from_unified_diff(&String::from_utf8_lossy(&diff))?
+        }
+        (None, Some(diff_base)) => {
+            ChangedLines::from_git_range(diff_base, config.source_rev.as_deref())?
+        }
+        (None, None) => return Ok(None),
     };
+    Ok(Some(changed_lines))
+}
 
-    cli_output.print_conclusion(&output.display().to_string());
-    Ok(())
+/// Coverage of the baseline report to compare with, if any. It is loaded
+/// like the inputs, so that its files have the same paths, and built with
+/// the sources of the report.
+fn load_baseline(config: &Config, context: &BuildContext) -> Result<Option<Baseline>, String> {
+    let Some(path) = &config.baseline else {
+        return Ok(None);
+    };
+    let input = config.with_input_settings(Input::from_path(path.clone()));
+    let (mut inputs, _) =
+        AggregatorInput::build_from_inputs(vec![vec![input]], &LocalFileSystem, false)
+            .map_err(|e| format!("Could not load the baseline: {}", e))?;
+    let input = inputs.pop().ok_or("Could not load the baseline")?;
+    Ok(Some(Baseline::from_input(input, context)))
+}
+
+/// Open the revision to read the sources from, if any
+fn open_source_rev(config: &Config) -> Result<Option<Rc<GitRevision>>, String> {
+    config
+        .source_rev
+        .as_deref()
+        .map(|revision| GitRevision::open(revision).map(Rc::new))
+        .transpose()
+}
+
+fn sum_removed_files(inputs: &[AggregatorInput]) -> RemovedFiles {
+    let mut removed_files = RemovedFiles::new();
+    for input in inputs {
+        for (filter, count) in input.get_removed_files() {
+            *removed_files.entry(filter.clone()).or_default() += count;
+        }
+    }
+    removed_files
+}
+
+/// Expand the glob patterns and directories of the inputs, and give them the
+/// path mappings and file filters of the configuration
+fn expand_config_inputs(
+    mut config: Config,
+    cli_output: &CliOutput<Console>,
+) -> Result<Config, String> {
+    let (inputs, skipped_inputs) =
+        expand_inputs(config.inputs, &LocalFileSystem, config.keep_going)
+            .map_err(|e| e.to_string())?;
+    print_skipped_inputs(skipped_inputs, cli_output);
+    config.inputs = inputs;
+    Ok(config.with_inputs_settings())
+}
+
+fn build_multi_report_root(
+    name: &str,
+    inputs: Vec<AggregatorInput>,
+    context: &BuildContext,
+) -> MultiReport {
+    let mut multi_report = MultiReport::new(name);
+    for input in inputs {
+        multi_report.add_report(TestedRoot::new(input, context));
+    }
+    if let Some(baseline) = &context.baseline {
+        multi_report.compare_with_baseline(baseline);
+    }
+    multi_report
+}
+
+fn build_single_report_root(
+    name: &str,
+    input: AggregatorInput,
+    context: &BuildContext,
+) -> TestedRoot {
+    TestedRoot::new(input.with_key("").with_name(name), context)
+}
+
+/// Root of the report: a single one when all inputs are in the same group,
+/// or one per group otherwise
+enum Root {
+    Single(TestedRoot),
+    Multi(MultiReport),
+}
+
+fn build_root(name: &str, mut inputs: Vec<AggregatorInput>, context: &BuildContext) -> Root {
+    if inputs.len() != 1 {
+        Root::Multi(build_multi_report_root(name, inputs, context))
+    } else {
+        Root::Single(build_single_report_root(name, inputs.remove(0), context))
+    }
+}
+
+impl Root {
+    /// Coverage of the whole root, its check against the thresholds, and the
+    /// coverage of its modules for the ratchet
+    fn check(&self, thresholds: &Thresholds, rules: &ThresholdRules) -> RootCheck {
+        match self {
+            Root::Single(root) => RootCheck::new(root, thresholds, rules),
+            Root::Multi(root) => RootCheck::new(root, thresholds, rules),
+        }
+    }
+
+    /// Render the root with the reporter, returning the sources that could
+    /// not be read
+    fn export(self, reporter: &Reporter, output: &PathBuf, rules: &ThresholdRules) -> Vec<PathBuf> {
+        match self {
+            Root::Single(root) => export_root(root, reporter, output, rules),
+            Root::Multi(root) => export_root(root, reporter, output, rules),
+        }
+    }
+}
+
+/// What the runs check of a root, rendered or not
+struct RootCheck {
+    coverage: AggregatedCoverage,
+    thresholds_check: ThresholdsCheck,
+    ratchet: Ratchet,
+}
+
+impl RootCheck {
+    fn new(root: &impl TestedContainer, thresholds: &Thresholds, rules: &ThresholdRules) -> Self {
+        RootCheck {
+            coverage: root.get_aggregated_coverage().clone(),
+            thresholds_check: ThresholdsCheck::new(thresholds, rules, root),
+            ratchet: Ratchet::from_root(root),
+        }
+    }
+}
+
+fn export_root(
+    root: impl TestedContainer,
+    reporter: &Reporter,
+    output: &PathBuf,
+    rules: &ThresholdRules,
+) -> Vec<PathBuf> {
+    match reporter {
+        Reporter::MpaHtmlLightReporter => MpaExporter::new(
+            HtmlLightRenderer::new(MpaLinksComputer).with_threshold_rules(rules.clone()),
+            root,
+            output,
+            &LocalFileSystem,
+        )
+        .render_root(),
+        Reporter::TextSummaryReporter => {
+            SpaExporter::new(TextSinglePageRenderer, root, output, &LocalFileSystem).render_root()
+        }
+    }
+}
+
+/// Print what all runs end with, telling whether the thresholds are reached
+fn finish_run(check: &RootCheck, cli_output: &CliOutput<Console>) -> bool {
+    cli_output.print_changed_lines(&check.coverage);
+    cli_output.print_thresholds(&check.thresholds_check);
+    check.thresholds_check.is_reached()
+}
+
+/// Export the root and tell what was generated
+fn export_and_finish(
+    root: Root,
+    config: &Config,
+    rules: &ThresholdRules,
+    cli_output: &CliOutput<Console>,
+) -> bool {
+    let check = root.check(&config.get_thresholds(), rules);
+    let missing_sources = root.export(&config.reporter, &config.output, rules);
+    cli_output.print_missing_sources(&missing_sources);
+    cli_output.print_conclusion(&config.output.display().to_string());
+    finish_run(&check, cli_output)
+}
+
+fn run_report(config: Config, cli_output: &CliOutput<Console>) -> Result<bool, String> {
+    let config = expand_config_inputs(config, cli_output)?;
+    let rules = ThresholdRules::new(&config.thresholds)?;
+    let input_groups = AggregatorInput::group_inputs(config.inputs.clone());
+    let context = load_build_context(&config)?;
+    let inputs = load_inputs(input_groups, &config, cli_output)?;
+
+    cli_output.print_introduction(&config, &sum_removed_files(&inputs));
+
+    let root = build_root(&config.name, inputs, &context);
+    Ok(export_and_finish(root, &config, &rules, cli_output))
+}
+
+fn run_merge(
+    merged_report_path: &Path,
+    config: Config,
+    cli_output: &CliOutput<Console>,
+) -> Result<bool, String> {
+    let config = expand_config_inputs(config, cli_output)?;
+    let rules = ThresholdRules::new(&config.thresholds)?;
+
+    // All inputs are merged together, whatever their group
+    let context = load_build_context(&config)?;
+    let mut inputs = load_inputs(vec![config.inputs.clone()], &config, cli_output)?;
+
+    cli_output.print_introduction(&config, &sum_removed_files(&inputs));
+
+    let merged_input = inputs.pop().ok_or("No input could be loaded")?;
+    LocalFileSystem
+        .write_all(merged_report_path, &merged_input.to_lcov_string())
+        .map_err(|e| e.to_string())?;
+    cli_output.print_merged_report(&merged_report_path.display().to_string());
+
+    let root = build_root(&config.name, vec![merged_input], &context);
+    Ok(export_and_finish(root, &config, &rules, cli_output))
+}
+
+/// Load the inputs to check their coverage without rendering them
+fn load_and_check_inputs(
+    config: Config,
+    cli_output: &CliOutput<Console>,
+) -> Result<RootCheck, String> {
+    let config = expand_config_inputs(config, cli_output)?;
+    let rules = ThresholdRules::new(&config.thresholds)?;
+    let input_groups = AggregatorInput::group_inputs(config.inputs.clone());
+    let context = load_build_context(&config)?;
+    let inputs = load_inputs(input_groups, &config, cli_output)?;
+
+    cli_output.print_check_introduction(&config, &sum_removed_files(&inputs));
+    let root = build_root(&config.name, inputs, &context);
+    Ok(root.check(&config.get_thresholds(), &rules))
+}
+
+fn run_check(config: Config, cli_output: &CliOutput<Console>) -> Result<bool, String> {
+    let check = load_and_check_inputs(config, cli_output)?;
+    if !check.thresholds_check.is_checked {
+        cli_output.print_warning("No threshold to check");
+    }
+
+    cli_output.print_coverage(&check.coverage);
+    Ok(finish_run(&check, cli_output))
+}
+
+/// Coverage stored in the ratchet file, if it exists
+fn read_ratchet(path: &Path) -> Result<Option<Ratchet>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid ratchet file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!(
+            "Could not read ratchet file {}: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Check that the coverage did not drop since the ratchet file, and raise it
+/// when asked to, unless the coverage dropped
+fn run_ratchet(
+    ratchet_path: &Path,
+    options: RatchetOptions,
+    config: Config,
+    cli_output: &CliOutput<Console>,
+) -> Result<bool, String> {
+    let stored = read_ratchet(ratchet_path)?;
+    let check = load_and_check_inputs(config, cli_output)?;
+    cli_output.print_coverage(&check.coverage);
+
+    let is_stored = stored.is_some();
+    if !is_stored && !options.update {
+        cli_output.print_warning(&format!(
+            "Ratchet file {} not found, create it with --update",
+            ratchet_path.display()
+        ));
+    }
+    let stored = stored.unwrap_or_default();
+    let drops = stored.check(&check.ratchet, options.tolerance as f64);
+    cli_output.print_coverage_drops(&drops);
+    let reached = finish_run(&check, cli_output) && drops.is_empty();
+
+    let raised = stored.raise(&check.ratchet);
+    if raised != stored {
+        match (options.update, reached) {
+            (true, true) => {
+                let content = toml::to_string(&raised).map_err(|e| e.to_string())?;
+                LocalFileSystem
+                    .write_all(ratchet_path, &content)
+                    .map_err(|e| e.to_string())?;
+                cli_output.print_ratchet_raised(&ratchet_path.display().to_string());
+            }
+            (true, false) => {
+                cli_output.print_warning("Ratchet file not raised, as the coverage check failed")
+            }
+            (false, _) if is_stored => cli_output.print_ratchet_hint(),
+            (false, _) => {}
+        }
+    }
+    Ok(reached)
 }
 
 fn save_config_to_file(config: Config, path: &PathBuf) -> Result<(), String> {
@@ -93,25 +405,41 @@ fn read_config_from_file(path: &PathBuf) -> Result<Config, String> {
     Ok(config)
 }
 
-fn run_command(args: Vec<String>, cli_output: &CliOutput<Console>) -> Result<(), String> {
+/// Run the command, telling whether the coverage reaches the thresholds
+fn run_command(args: Vec<String>, cli_output: &CliOutput<Console>) -> Result<bool, String> {
     let command = CliConfigParser::new().parse(&args)?.build()?;
-    match command {
-        CliCommand::Report(config) => run_report(config, &cli_output)?,
-        CliCommand::ToFile(path, config) => save_config_to_file(config, &path)?,
+    let thresholds_reached = match command {
+        CliCommand::Report(config) => run_report(config, cli_output)?,
+        CliCommand::ToFile(path, config) => save_config_to_file(config, &path).map(|_| true)?,
+        CliCommand::Merge(path, config) => run_merge(&path, config, cli_output)?,
+        CliCommand::Check(config) => run_check(config, cli_output)?,
+        CliCommand::Ratchet(path, options, config) => {
+            run_ratchet(&path, options, config, cli_output)?
+        }
         CliCommand::FromFile(path) => {
             let config = read_config_from_file(&path)?;
-            run_report(config, &cli_output)?
+            run_report(config, cli_output)?
+        }
+        CliCommand::Help(command) if command.is_empty() => {
+            cli_output.print_help();
+            true
+        }
+        CliCommand::Help(command) => {
+            cli_output.print_command_help(&command);
+            true
         }
-        CliCommand::Help(command) if command.is_empty() => cli_output.print_help(),
-        CliCommand::Help(command) => cli_output.print_command_help(&command),
     };
-    Ok(())
+    Ok(thresholds_reached)
 }
 
-pub fn run(args: Vec<String>) -> () {
+pub fn run(args: Vec<String>) -> ExitCode {
     let cli_output = CliOutput::new(Console);
     match run_command(args, &cli_output) {
-        Ok(_) => (),
-        Err(e) => cli_output.print_error(&e),
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(THRESHOLDS_FAILURE),
+        Err(e) => {
+            cli_output.print_error(&e);
+            ExitCode::FAILURE
+        }
     }
 }